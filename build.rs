@@ -0,0 +1,17 @@
+// Records the git commit being built, for version_info. Builds outside a
+// git checkout simply go without.
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output();
+    if let Ok(output) = output {
+        if output.status.success() {
+            let commit = String::from_utf8_lossy(&output.stdout);
+            println!("cargo:rustc-env=SANMOKU_GIT_COMMIT={}", commit.trim());
+        }
+    }
+}
@@ -0,0 +1,94 @@
+// Difficulty that follows the user's results over a session: winning makes
+// the computer stronger, losing makes it weaker.
+use crate::model::{GameStatus, Player};
+use crate::rng::GameRng;
+use crate::strategy::Strategy;
+
+pub const MAX_RATING: u32 = 3;
+pub const INITIAL_RATING: u32 = 1;
+
+// Difficulty tier for a rating, weakest first.
+pub fn strategy_for_rating(rating: u32) -> Strategy {
+    match rating.min(MAX_RATING) {
+        0 => Strategy::Random,
+        1 => Strategy::WeightedRandom,
+        2 => Strategy::Heuristic {
+            aggressiveness: 0.5,
+        },
+        _ => Strategy::Optimal {
+            randomize_ties: true,
+        },
+    }
+}
+
+// Rating for the next game after a game ended with `status`.
+pub fn adjust_rating(rating: u32, status: GameStatus) -> u32 {
+    match status {
+        GameStatus::Settled(Player::User) => (rating + 1).min(MAX_RATING),
+        GameStatus::Settled(Player::Computer) => rating.saturating_sub(1),
+        _ => rating,
+    }
+}
+
+// Any of the difficulty tiers, equally likely, for --shuffle-difficulty.
+#[cfg(feature = "random")]
+pub fn shuffled_strategy(rng: &mut GameRng) -> Strategy {
+    use rand::Rng;
+    strategy_for_rating(rng.gen_range(0, MAX_RATING + 1))
+}
+
+// Config refuses --shuffle-difficulty in this build; the strongest tier is
+// the only one that doesn't depend on chance.
+#[cfg(not(feature = "random"))]
+pub fn shuffled_strategy(_rng: &mut GameRng) -> Strategy {
+    strategy_for_rating(MAX_RATING)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratings_map_to_tiers_weakest_first() {
+        assert_eq!(strategy_for_rating(0), Strategy::Random);
+        assert_eq!(strategy_for_rating(1), Strategy::WeightedRandom);
+        assert_eq!(strategy_for_rating(2).name(), "heuristic");
+        let strongest = Strategy::Optimal {
+            randomize_ties: true,
+        };
+        assert_eq!(strategy_for_rating(MAX_RATING), strongest);
+        assert_eq!(strategy_for_rating(MAX_RATING + 5), strongest);
+    }
+
+    #[test]
+    fn wins_raise_the_rating_and_losses_lower_it() {
+        let win = GameStatus::Settled(Player::User);
+        let loss = GameStatus::Settled(Player::Computer);
+        assert_eq!(adjust_rating(INITIAL_RATING, win), INITIAL_RATING + 1);
+        assert_eq!(adjust_rating(INITIAL_RATING, loss), INITIAL_RATING - 1);
+        assert_eq!(
+            adjust_rating(INITIAL_RATING, GameStatus::Draw),
+            INITIAL_RATING
+        );
+        assert_eq!(adjust_rating(MAX_RATING, win), MAX_RATING);
+        assert_eq!(adjust_rating(0, loss), 0);
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn seeded_sessions_shuffle_the_same_way() {
+        use crate::rng::seeded;
+        let session = |seed| {
+            let mut rng = seeded(Some(seed));
+            (0..20)
+                .map(|_| shuffled_strategy(&mut rng))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(session(7), session(7));
+        // Over a session every tier comes up
+        let tiers = session(7);
+        for rating in 0..=MAX_RATING {
+            assert!(tiers.contains(&strategy_for_rating(rating)));
+        }
+    }
+}
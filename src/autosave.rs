@@ -0,0 +1,82 @@
+// Saving the game in progress on Ctrl-C so it can be resumed on the next
+// launch, available when built with `--features autosave`.
+use crate::commands::parse_move_log;
+use crate::config::{config_file_path, Config};
+use crate::model::{GameStatus, Model, Player};
+use crate::update::apply_moves;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// Latest state of the game in progress, None when there is nothing to save.
+pub type Snapshot = Arc<Mutex<Option<String>>>;
+
+// Kept next to the config file.
+pub fn autosave_path() -> Option<PathBuf> {
+    Some(config_file_path()?.with_file_name("autosave.txt"))
+}
+
+// Who went first on the first line, the cells played on the second.
+pub fn encode_game(model: &Model) -> Option<String> {
+    let first = match model.first_player? {
+        Player::User => "user",
+        Player::Computer => "computer",
+    };
+    let moves: Vec<String> = model.history.iter().map(|i| i.to_string()).collect();
+    Some(format!("{}\n{}\n", first, moves.join(" ")))
+}
+
+// Rebuilds a saved game under `config`. None unless the moves are legal and
+// it's the user's turn in an unfinished game.
+pub fn decode_game(text: &str, config: Config) -> Option<Model> {
+    let mut lines = text.lines();
+    let first_player = match lines.next()? {
+        "user" => Player::User,
+        "computer" => Player::Computer,
+        _ => return None,
+    };
+    let moves = parse_move_log(lines.next().unwrap_or(""))?;
+    let mut model = Model::new(config);
+    model.first_player = Some(first_player);
+    let model = apply_moves(model, &moves).ok()?;
+    let users_turn = model.player_of_move(model.history.len()) == Some(Player::User);
+    if model.status == GameStatus::NotFinished && users_turn {
+        Some(model)
+    } else {
+        None
+    }
+}
+
+// Writes to a temporary file first so an interrupted write never leaves a
+// truncated save behind.
+pub fn write_atomically(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
+
+// On Ctrl-C, saves the snapshot (if any) and exits.
+#[cfg(feature = "autosave")]
+pub fn install_interrupt_handler(snapshot: Snapshot) -> Result<(), String> {
+    ctrlc::set_handler(move || {
+        let saved = snapshot.lock().ok().and_then(|s| s.clone());
+        match (saved, autosave_path()) {
+            (Some(text), Some(path)) => match write_atomically(&path, &text) {
+                Ok(()) => println!("\nGame saved to {}. Goodbye!", path.display()),
+                Err(e) => eprintln!("\nFailed to save {}: {}", path.display(), e),
+            },
+            _ => println!("\nGoodbye!"),
+        }
+        std::process::exit(130);
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "autosave"))]
+pub fn install_interrupt_handler(_snapshot: Snapshot) -> Result<(), String> {
+    Err("built without autosave support".to_string())
+}
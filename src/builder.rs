@@ -0,0 +1,126 @@
+// Fluent construction of a ready-to-play Model for library users.
+use crate::config::{Config, Layout};
+use crate::model::{Model, Player};
+use crate::rules::{DoubleWin, WinCondition};
+use crate::strategy::Strategy;
+use crate::update::update_player_selection;
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug, PartialEq)]
+pub enum BuildError {
+    CellOutOfRange(usize),
+    AggressivenessOutOfRange(f32),
+    ErrorRateOutOfRange(f32),
+    NoFreeCells,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::CellOutOfRange(i) => write!(f, "cell {} is not on the board", i),
+            BuildError::AggressivenessOutOfRange(a) => {
+                write!(f, "aggressiveness {} is not between 0.0 and 1.0", a)
+            }
+            BuildError::ErrorRateOutOfRange(p) => {
+                write!(f, "error rate {} is not between 0.0 and 1.0", p)
+            }
+            BuildError::NoFreeCells => write!(f, "every cell is blocked"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GameBuilder {
+    config: Config,
+    first_player: Option<Player>,
+    win_conditions: Option<Vec<Arc<dyn WinCondition>>>,
+    double_win: DoubleWin,
+}
+
+impl GameBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Starting from existing options, e.g. ones parsed from the command line.
+    pub fn from_config(config: Config) -> Self {
+        Self {
+            config,
+            first_player: None,
+            win_conditions: None,
+            double_win: DoubleWin::default(),
+        }
+    }
+
+    pub fn first_player(mut self, player: Player) -> Self {
+        self.first_player = Some(player);
+        self
+    }
+
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.config.strategy = strategy;
+        self
+    }
+
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.config.layout = layout;
+        self
+    }
+
+    pub fn misere(mut self, misere: bool) -> Self {
+        self.config.misere = misere;
+        self
+    }
+
+    pub fn blocked(mut self, cells: &[usize]) -> Self {
+        self.config.blocked = cells.to_vec();
+        self
+    }
+
+    // Registers what settles the game, in place of the line (or misère
+    // line) the rules give. Checked in this order.
+    pub fn win_conditions(mut self, conditions: Vec<Arc<dyn WinCondition>>) -> Self {
+        self.win_conditions = Some(conditions);
+        self
+    }
+
+    // Who wins when the conditions make both sides winners at once.
+    pub fn double_win(mut self, policy: DoubleWin) -> Self {
+        self.double_win = policy;
+        self
+    }
+
+    // With a first player set, the game is returned ready for the user's
+    // move (the computer has already opened if it goes first).
+    pub fn build(self) -> Result<Model, BuildError> {
+        if let Some(&i) = self.config.blocked.iter().find(|&&i| i >= 9) {
+            return Err(BuildError::CellOutOfRange(i));
+        }
+        let mut blocked = self.config.blocked.clone();
+        blocked.sort_unstable();
+        blocked.dedup();
+        if blocked.len() == 9 {
+            return Err(BuildError::NoFreeCells);
+        }
+        if let Strategy::Heuristic { aggressiveness } = self.config.strategy {
+            if !(0.0..=1.0).contains(&aggressiveness) {
+                return Err(BuildError::AggressivenessOutOfRange(aggressiveness));
+            }
+        }
+        if let Strategy::Human { error_rate } = self.config.strategy {
+            if !(0.0..=1.0).contains(&error_rate) {
+                return Err(BuildError::ErrorRateOutOfRange(error_rate));
+            }
+        }
+        let mut model = Model::new(self.config);
+        if let Some(conditions) = self.win_conditions {
+            model.win_conditions = conditions;
+        }
+        model.double_win = self.double_win;
+        Ok(match self.first_player {
+            Some(player) => update_player_selection(model, player == Player::User),
+            None => model,
+        })
+    }
+}
@@ -0,0 +1,112 @@
+// Positions the user bookmarked with a label, kept next to the config file
+// as a personal puzzle set.
+use crate::autosave::write_atomically;
+use crate::config::config_file_path;
+use crate::model::{Board, Cell};
+use crate::notation::{parse_notation, to_notation};
+use crate::symmetry::is_equivalent;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub board: Board,
+    pub label: String,
+}
+
+impl Bookmark {
+    // Nought moves first, so the side to move follows from the counts.
+    pub fn to_move(&self) -> Cell {
+        let count = |symbol| self.board.iter().filter(|&&c| c == symbol).count();
+        if count(Cell::Nought) > count(Cell::Cross) {
+            Cell::Cross
+        } else {
+            Cell::Nought
+        }
+    }
+
+    pub fn notation(&self) -> String {
+        to_notation(&self.board, self.to_move())
+    }
+}
+
+pub fn collection_path() -> Option<PathBuf> {
+    Some(config_file_path()?.with_file_name("collection.txt"))
+}
+
+// One tab separated `notation label` line per position; lines that don't
+// parse are skipped.
+pub fn parse_collection(text: &str) -> Vec<Bookmark> {
+    text.lines()
+        .filter_map(|line| {
+            let (notation, label) = line.split_once('\t')?;
+            let (board, _) = parse_notation(notation).ok()?;
+            Some(Bookmark {
+                board,
+                label: label.to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn format_collection(bookmarks: &[Bookmark]) -> String {
+    bookmarks
+        .iter()
+        .map(|b| format!("{}\t{}\n", b.notation(), b.label))
+        .collect()
+}
+
+// A missing file is an empty collection.
+pub fn load_collection(path: &Path) -> io::Result<Vec<Bookmark>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(parse_collection(&text)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn save_collection(path: &Path, bookmarks: &[Bookmark]) -> io::Result<()> {
+    write_atomically(path, format_collection(bookmarks))
+}
+
+// Adds the position unless a rotation or reflection of it is already
+// collected. Returns whether it was added.
+pub fn add_to_collection(bookmarks: &mut Vec<Bookmark>, board: &Board, label: &str) -> bool {
+    if bookmarks.iter().any(|b| is_equivalent(&b.board, board)) {
+        return false;
+    }
+    bookmarks.push(Bookmark {
+        board: *board,
+        label: label.to_string(),
+    });
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symmetric_positions_are_stored_once() {
+        let (corner, _) = parse_notation("o../.x./... o").unwrap();
+        let (rotated, _) = parse_notation("..o/.x./... o").unwrap();
+        let (edge, _) = parse_notation(".o./.x./... o").unwrap();
+        let mut bookmarks = Vec::new();
+        assert!(add_to_collection(&mut bookmarks, &corner, "corner"));
+        assert!(!add_to_collection(&mut bookmarks, &rotated, "again"));
+        assert!(add_to_collection(&mut bookmarks, &edge, "edge"));
+        let labels: Vec<&str> = bookmarks.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, ["corner", "edge"]);
+    }
+
+    #[test]
+    fn collection_round_trips_through_text() {
+        let mut bookmarks = Vec::new();
+        let (board, _) = parse_notation("o../.x./..o x").unwrap();
+        add_to_collection(&mut bookmarks, &board, "opposite corners");
+        let text = format_collection(&bookmarks);
+        assert_eq!(text, "o../.x./..o x\topposite corners\n");
+        assert_eq!(parse_collection(&text), bookmarks);
+    }
+}
@@ -0,0 +1,331 @@
+// Subcommands of the command line. Without one the program plays, so the
+// old `sanmoku --flags` form keeps working.
+use crate::config::{Config, Layout};
+use crate::rules::Rules;
+use crate::solver::Outcome;
+use crate::strategy::Strategy;
+
+pub const USAGE: &str = "Usage:
+  sanmoku [play] [options]         play against the computer (see README for options)
+  sanmoku --version                print the version and build details
+  sanmoku daily [options]          play today's challenge, the same for everyone
+  sanmoku analyze <position> [--layout numpad] [--misere]
+                                   evaluate a position, e.g. \"ox./.../... o\"
+  sanmoku replay <log> [--goto <n>] [--animate [--delay <ms>]] [--layout numpad]
+                                   step through a game given as cells 0-8,
+                                   jump to the board after move n, or play it
+                                   back move by move
+  sanmoku batch <file> [--layout numpad] [--misere]
+                                   evaluate one position per line
+  sanmoku puzzles [<number>] [--layout numpad]
+                                   list the built-in puzzles or solve one
+  sanmoku collections [<number>] [--layout numpad]
+                                   list your bookmarked positions or analyze one
+  sanmoku simulate <position> [--nought <strategy>] [--cross <strategy>]
+                   [--seed <n>] [--layout numpad] [--misere]
+                                   play a position out computer against computer
+  sanmoku match-strategies <strategy> <strategy> [--games <n>] [--seed <n>] [--misere]
+                                   play two strategies against each other,
+                                   taking turns to start
+  sanmoku verify <position> <line> [--outcome win|draw|loss] [--layout numpad] [--misere]
+                                   check that every move of a line keeps the
+                                   best result";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Version,
+    Play(Vec<String>),  // game options, parsed by Config::from_args
+    Daily(Vec<String>), // the same, for the daily challenge
+    Analyze {
+        position: String,
+        layout: Layout,
+        rules: Rules,
+    },
+    Replay {
+        path: String,
+        goto: Option<usize>,  // only show the board after this many moves
+        animate: Option<u64>, // redraw the board after each move, waiting this many ms
+        layout: Layout,
+    },
+    Batch {
+        path: String,
+        layout: Layout,
+        rules: Rules,
+    },
+    Puzzles {
+        number: Option<usize>,
+        layout: Layout,
+    },
+    Collections {
+        number: Option<usize>,
+        layout: Layout,
+    },
+    Verify {
+        position: String,
+        line: String, // cell labels, separated by spaces or commas
+        outcome: Option<Outcome>,
+        layout: Layout,
+        rules: Rules,
+    },
+    MatchStrategies {
+        first: Strategy,
+        second: Strategy,
+        games: usize,
+        seed: u64,
+        rules: Rules,
+    },
+    Simulate {
+        position: String,
+        nought: Strategy,
+        cross: Strategy,
+        seed: Option<u64>,
+        layout: Layout,
+        rules: Rules,
+    },
+}
+
+fn layout_named(name: &str) -> Result<Layout, String> {
+    match name {
+        "standard" => Ok(Layout::Standard),
+        "numpad" => Ok(Layout::Numpad),
+        _ => Err("--layout expects 'standard' or 'numpad'".to_string()),
+    }
+}
+
+// The only option shared by the subcommands that show boards.
+fn parse_layout_option(args: &[String]) -> Result<Layout, String> {
+    match args {
+        [] => Ok(Layout::Standard),
+        [flag, value] if flag == "--layout" => layout_named(value),
+        _ => Err(format!("Unexpected arguments: {}", args.join(" "))),
+    }
+}
+
+// The layout and the rules of the subcommands that evaluate positions, in
+// either order.
+fn parse_analysis_options(args: &[String]) -> Result<(Layout, Rules), String> {
+    let mut layout = Layout::Standard;
+    let mut rules = Rules::Standard;
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        match flag.as_str() {
+            "--layout" => layout = layout_named(iter.next().ok_or("--layout expects a value")?)?,
+            "--misere" => rules = Rules::Misere,
+            _ => return Err(format!("Unexpected arguments: {}", args.join(" "))),
+        }
+    }
+    Ok((layout, rules))
+}
+
+// Options of `simulate`: a strategy for each side (optimal by default), the
+// seed, the layout and the rules. Strategy names are the ones --strategy
+// takes.
+fn parse_simulate(position: String, args: &[String]) -> Result<Command, String> {
+    let strategy = |name: &str| {
+        Config::from_args(&["--strategy".to_string(), name.to_string()]).map(|c| c.strategy)
+    };
+    let mut nought = strategy("optimal")?;
+    let mut cross = nought;
+    let mut seed = None;
+    let mut layout = Layout::Standard;
+    let mut rules = Rules::Standard;
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        if flag == "--misere" {
+            rules = Rules::Misere;
+            continue;
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("{} expects a value", flag))?;
+        match flag.as_str() {
+            "--nought" => nought = strategy(value)?,
+            "--cross" => cross = strategy(value)?,
+            "--seed" => seed = Some(value.parse().map_err(|_| "--seed expects a number")?),
+            "--layout" => layout = layout_named(value)?,
+            _ => return Err(format!("Unexpected argument: {}", flag)),
+        }
+    }
+    Ok(Command::Simulate {
+        position,
+        nought,
+        cross,
+        seed,
+        layout,
+        rules,
+    })
+}
+
+const DEFAULT_ANIMATION_DELAY_MS: u64 = 800;
+
+// Arguments of `replay`: the log file and its options, in any order.
+fn parse_replay(args: &[String]) -> Result<Command, String> {
+    let mut path = None;
+    let mut goto = None;
+    let mut animate = false;
+    let mut delay = DEFAULT_ANIMATION_DELAY_MS;
+    let mut layout = Layout::Standard;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .ok_or_else(|| format!("{} expects a value", arg))
+        };
+        match arg.as_str() {
+            "--goto" => {
+                goto = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| "--goto expects a move number")?,
+                )
+            }
+            "--animate" => animate = true,
+            "--delay" => {
+                delay = value()?
+                    .parse()
+                    .map_err(|_| "--delay expects milliseconds")?
+            }
+            "--layout" => layout = layout_named(value()?)?,
+            _ if path.is_none() && !arg.starts_with('-') => path = Some(arg.clone()),
+            _ => return Err(format!("Unexpected argument: {}", arg)),
+        }
+    }
+    if animate && goto.is_some() {
+        return Err("replay takes --goto or --animate, not both".to_string());
+    }
+    Ok(Command::Replay {
+        path: path.ok_or("replay expects a log file")?,
+        goto,
+        animate: if animate { Some(delay) } else { None },
+        layout,
+    })
+}
+
+const DEFAULT_MATCH_GAMES: usize = 100;
+
+// Arguments of `match-strategies`: two strategy names as --strategy takes
+// them, then the number of games, the seed and the rules.
+fn parse_match(args: &[String]) -> Result<Command, String> {
+    let strategy = |name: &str| {
+        Config::from_args(&["--strategy".to_string(), name.to_string()]).map(|c| c.strategy)
+    };
+    let (first, second, options) = match args {
+        [first, second, options @ ..] => (strategy(first)?, strategy(second)?, options),
+        _ => return Err("match-strategies expects two strategies".to_string()),
+    };
+    let mut games = DEFAULT_MATCH_GAMES;
+    let mut seed = 0;
+    let mut rules = Rules::Standard;
+    let mut iter = options.iter();
+    while let Some(flag) = iter.next() {
+        if flag == "--misere" {
+            rules = Rules::Misere;
+            continue;
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("{} expects a value", flag))?;
+        match flag.as_str() {
+            "--games" => games = value.parse().map_err(|_| "--games expects a number")?,
+            "--seed" => seed = value.parse().map_err(|_| "--seed expects a number")?,
+            _ => return Err(format!("Unexpected argument: {}", flag)),
+        }
+    }
+    Ok(Command::MatchStrategies {
+        first,
+        second,
+        games,
+        seed,
+        rules,
+    })
+}
+
+pub fn parse_command(args: &[String]) -> Result<Command, String> {
+    if let [flag] = args {
+        if flag == "--version" {
+            return Ok(Command::Version);
+        }
+    }
+    let (name, rest) = match args.split_first() {
+        Some((name, rest)) if !name.starts_with('-') => (name.as_str(), rest),
+        _ => return Ok(Command::Play(args.to_vec())),
+    };
+    let operand = |what: &str| {
+        rest.first()
+            .cloned()
+            .ok_or_else(|| format!("{} expects {}", name, what))
+    };
+    let options = rest.get(1..).unwrap_or(&[]);
+    match name {
+        "play" => Ok(Command::Play(rest.to_vec())),
+        "daily" => Ok(Command::Daily(rest.to_vec())),
+        "analyze" => {
+            let position = operand("a position")?;
+            let (layout, rules) = parse_analysis_options(options)?;
+            Ok(Command::Analyze {
+                position,
+                layout,
+                rules,
+            })
+        }
+        "replay" => parse_replay(rest),
+        "batch" => {
+            let path = operand("a file")?;
+            let (layout, rules) = parse_analysis_options(options)?;
+            Ok(Command::Batch {
+                path,
+                layout,
+                rules,
+            })
+        }
+        "simulate" => parse_simulate(operand("a position")?, options),
+        "match-strategies" => parse_match(rest),
+        "verify" => {
+            let position = operand("a position and a line of moves")?;
+            let line = options
+                .first()
+                .cloned()
+                .ok_or("verify expects a line of moves after the position")?;
+            let options = &options[1..];
+            let (outcome, options) = match options {
+                [flag, name, rest @ ..] if flag == "--outcome" => {
+                    let outcome = match name.as_str() {
+                        "win" => Outcome::Win,
+                        "draw" => Outcome::Draw,
+                        "loss" => Outcome::Loss,
+                        _ => return Err("--outcome expects win, draw or loss".to_string()),
+                    };
+                    (Some(outcome), rest)
+                }
+                _ => (None, options),
+            };
+            let (layout, rules) = parse_analysis_options(options)?;
+            Ok(Command::Verify {
+                position,
+                line,
+                outcome,
+                layout,
+                rules,
+            })
+        }
+        "puzzles" | "collections" => {
+            let number = rest.first().and_then(|s| s.parse().ok());
+            let options = if number.is_some() { options } else { rest };
+            let layout = parse_layout_option(options)?;
+            Ok(if name == "puzzles" {
+                Command::Puzzles { number, layout }
+            } else {
+                Command::Collections { number, layout }
+            })
+        }
+        _ => Err(format!("Unknown command: {}", name)),
+    }
+}
+
+// Cells of a game log, separated by whitespace or commas.
+pub fn parse_move_log(text: &str) -> Option<Vec<usize>> {
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse::<usize>().ok().filter(|&i| i < 9))
+        .collect()
+}
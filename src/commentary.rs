@@ -0,0 +1,177 @@
+// Game narration. The view asks a Commentator for the words; swapping in
+// another one changes the style without touching the game.
+use crate::helpers::threats;
+use crate::model::{Cell, GameStatus, Model, Player};
+use crate::theme::{Locale, Theme};
+use crate::view::cell_name;
+
+pub trait Commentator {
+    // `label` is the cell as the user names it.
+    fn move_made(&mut self, player: Player, symbol: Cell, label: usize) -> String;
+
+    // `labels` are the cells where `player` now threatens to complete a
+    // line. Nothing is said by default.
+    fn threat_created(&mut self, _player: Player, _labels: &[usize]) -> Option<String> {
+        None
+    }
+
+    fn win(&mut self, winner: Player) -> String;
+
+    fn draw(&mut self) -> String;
+}
+
+// The game's usual messages, with the results as the English theme words
+// them.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct StandardCommentator;
+
+impl Commentator for StandardCommentator {
+    fn move_made(&mut self, player: Player, symbol: Cell, label: usize) -> String {
+        let who = match player {
+            Player::User => "You",
+            Player::Computer => "Computer",
+        };
+        format!("{} played {} at cell {}", who, cell_name(symbol), label)
+    }
+
+    fn win(&mut self, winner: Player) -> String {
+        ThemedCommentator(&Theme::for_locale(Locale::English)).win(winner)
+    }
+
+    fn draw(&mut self) -> String {
+        ThemedCommentator(&Theme::for_locale(Locale::English)).draw()
+    }
+}
+
+// The usual messages, with the results worded by a theme.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ThemedCommentator<'a>(pub &'a Theme);
+
+impl Commentator for ThemedCommentator<'_> {
+    fn move_made(&mut self, player: Player, symbol: Cell, label: usize) -> String {
+        StandardCommentator.move_made(player, symbol, label)
+    }
+
+    fn win(&mut self, winner: Player) -> String {
+        match winner {
+            Player::User => self.0.win.clone(),
+            Player::Computer => self.0.loss.clone(),
+        }
+    }
+
+    fn draw(&mut self) -> String {
+        self.0.draw.clone()
+    }
+}
+
+// Comments on the moves since the user's last one, each followed by any
+// threat it created.
+pub fn narrate_moves(model: &Model, commentator: &mut dyn Commentator) -> Vec<String> {
+    let layout = model.config.layout;
+    let mut lines = Vec::new();
+    for k in model.recent_moves_start()..model.history.len() {
+        let player = match model.player_of_move(k) {
+            Some(player) => player,
+            None => continue,
+        };
+        let symbol = model.symbol_of_move(k);
+        lines.push(commentator.move_made(player, symbol, layout.label_of(model.history[k])));
+        let before = threats(&model.board_after_moves(k), symbol);
+        let created: Vec<usize> = threats(&model.board_after_moves(k + 1), symbol)
+            .into_iter()
+            .filter(|i| !before.contains(i))
+            .map(|i| layout.label_of(i))
+            .collect();
+        if !created.is_empty() {
+            lines.extend(commentator.threat_created(player, &created));
+        }
+    }
+    lines
+}
+
+// None while the game is still going.
+pub fn narrate_result(model: &Model, commentator: &mut dyn Commentator) -> Option<String> {
+    match model.status {
+        GameStatus::Settled(winner) => Some(commentator.win(winner)),
+        GameStatus::Draw => Some(commentator.draw()),
+        GameStatus::NotFinished => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    // Writes down what it was asked to say.
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl Commentator for Recorder {
+        fn move_made(&mut self, player: Player, symbol: Cell, label: usize) -> String {
+            let line = format!("{:?} {} {}", player, symbol, label);
+            self.0.push(line.clone());
+            line
+        }
+
+        fn threat_created(&mut self, player: Player, labels: &[usize]) -> Option<String> {
+            let line = format!("{:?} threatens {:?}", player, labels);
+            self.0.push(line.clone());
+            Some(line)
+        }
+
+        fn win(&mut self, winner: Player) -> String {
+            format!("{:?} wins", winner)
+        }
+
+        fn draw(&mut self) -> String {
+            "drawn".to_string()
+        }
+    }
+
+    fn game(moves: &[usize]) -> Model {
+        let mut model = Model::new(Config::default());
+        model.first_player = Some(Player::User);
+        for &i in moves {
+            model.fill(i, model.symbol_of_move(model.history.len()));
+            model.history.push(i);
+        }
+        model
+    }
+
+    #[test]
+    fn moves_and_threats_are_narrated_in_order() {
+        // The user's o on 1 threatens 2; the computer's x there threatens 6
+        let model = game(&[0, 4, 1, 2]);
+        let mut recorder = Recorder::default();
+        let lines = narrate_moves(&model, &mut recorder);
+        let expected = [
+            "User o 1",
+            "User threatens [2]",
+            "Computer x 2",
+            "Computer threatens [6]",
+        ];
+        assert_eq!(lines, expected);
+        assert_eq!(recorder.0, expected);
+    }
+
+    #[test]
+    fn results_are_narrated_once_the_game_ends() {
+        let mut model = game(&[0, 4, 1]);
+        assert_eq!(narrate_result(&model, &mut Recorder::default()), None);
+        model.status = GameStatus::Settled(Player::Computer);
+        let result = narrate_result(&model, &mut Recorder::default());
+        assert_eq!(result.as_deref(), Some("Computer wins"));
+        model.status = GameStatus::Draw;
+        let result = narrate_result(&model, &mut Recorder::default());
+        assert_eq!(result.as_deref(), Some("drawn"));
+    }
+
+    #[test]
+    fn standard_results_match_the_english_theme() {
+        let theme = Theme::default();
+        assert_eq!(StandardCommentator.win(Player::User), theme.win);
+        assert_eq!(StandardCommentator.win(Player::Computer), theme.loss);
+        assert_eq!(StandardCommentator.draw(), theme.draw);
+    }
+}
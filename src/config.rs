@@ -0,0 +1,791 @@
+use crate::export::MAX_DOT_DEPTH;
+use crate::model::{Board, Cell, Player};
+use crate::notation::parse_notation;
+use crate::positions::is_terminal;
+use crate::rules::Rules;
+use crate::strategy::Strategy;
+use crate::symmetry::Transform;
+use crate::theme::{Locale, Theme};
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+
+// How cell indices are labeled for the user. Internally cells are always 0-8.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Layout {
+    Standard, // 0|1|2 on the top row
+    Numpad,   // 7|8|9 on the top row, like a numeric keypad
+    // The same labels on a board displayed flipped left to right
+    StandardMirrored,
+    NumpadMirrored,
+}
+
+const NUMPAD_LABELS: [usize; 9] = [7, 8, 9, 4, 5, 6, 1, 2, 3];
+
+impl Layout {
+    pub fn mirrored(self) -> Layout {
+        match self {
+            Layout::Standard => Layout::StandardMirrored,
+            Layout::Numpad => Layout::NumpadMirrored,
+            mirrored => mirrored,
+        }
+    }
+
+    pub fn is_mirrored(self) -> bool {
+        matches!(self, Layout::StandardMirrored | Layout::NumpadMirrored)
+    }
+
+    // Internal index of the cell drawn at display position `position` (0-8,
+    // row by row). Mirroring is its own inverse, so this also maps an
+    // internal index to where it is drawn.
+    pub fn cell_at(self, position: usize) -> usize {
+        if self.is_mirrored() {
+            Transform::ReflectHorizontal.map_index(position)
+        } else {
+            position
+        }
+    }
+
+    // Labels follow the displayed position, so they read the same way
+    // whether or not the board is mirrored.
+    pub fn label_of(self, index: usize) -> usize {
+        let position = self.cell_at(index);
+        match self {
+            Layout::Standard | Layout::StandardMirrored => position,
+            Layout::Numpad | Layout::NumpadMirrored => NUMPAD_LABELS[position],
+        }
+    }
+
+    pub fn index_of(self, label: usize) -> Option<usize> {
+        (0..9).find(|&i| self.label_of(i) == label)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Layout::Standard | Layout::StandardMirrored => "standard",
+            Layout::Numpad | Layout::NumpadMirrored => "numpad",
+        }
+    }
+
+    pub fn label_range(self) -> &'static str {
+        match self {
+            Layout::Standard | Layout::StandardMirrored => "[0-8]",
+            Layout::Numpad | Layout::NumpadMirrored => "[1-9]",
+        }
+    }
+}
+
+// How the grid is drawn in the terminal.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RenderStyle {
+    Compact,  // one character per cell, empty cells showing their label
+    Standard, // the label guide next to the board, as cells joined by |
+    Spacious, // padded cells with separator rows
+    Unicode,  // box-drawing characters
+}
+
+impl RenderStyle {
+    pub fn from_name(name: &str) -> Option<RenderStyle> {
+        match name {
+            "compact" => Some(RenderStyle::Compact),
+            "standard" => Some(RenderStyle::Standard),
+            "spacious" => Some(RenderStyle::Spacious),
+            "unicode" => Some(RenderStyle::Unicode),
+            _ => None,
+        }
+    }
+}
+
+// What completing a line does. Anything but End is arcade play: the line
+// scores for its owner and the game goes on until the board is full.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineResolution {
+    End,   // the standard game: the line wins
+    Clear, // the line's cells are emptied again
+    Lock,  // the line's cells are blocked for the rest of the game
+}
+
+impl LineResolution {
+    pub fn from_name(name: &str) -> Option<LineResolution> {
+        match name {
+            "end" => Some(LineResolution::End),
+            "clear" => Some(LineResolution::Clear),
+            "lock" => Some(LineResolution::Lock),
+            _ => None,
+        }
+    }
+}
+
+const DEFAULT_AGGRESSIVENESS: f32 = 0.5;
+const DEFAULT_ERROR_RATE: f32 = 0.2;
+
+#[cfg(feature = "random")]
+const DEFAULT_STRATEGY: Strategy = Strategy::Random;
+// Without the random feature the solver is the computer
+#[cfg(not(feature = "random"))]
+const DEFAULT_STRATEGY: Strategy = Strategy::Optimal {
+    randomize_ties: false,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Config {
+    pub layout: Layout,
+    // 0 quiet, 1 announce moves and forks, 2 also evaluations, 3 also the
+    // best line of play
+    pub verbosity: u8,
+    pub export_svg: Option<String>,
+    pub export_png: Option<String>,
+    pub export_dot: Option<String>, // write the game tree from the start position and exit
+    pub dot_depth: usize,
+    pub think_delay_ms: u64, // 0 disables the pause before the computer's move
+    pub auto_forced: bool,
+    pub accessible: bool, // describe the board in prose instead of a grid
+    pub blindfold: bool,  // hide the board until the game ends
+    pub render_style: RenderStyle,
+    pub move_numbers: bool,  // show the order of moves once the game ends
+    pub misere: bool,        // completing a line loses instead of wins
+    pub stats: bool,         // print facts about the game and exit
+    pub info: bool,          // print the rules and settings and exit
+    pub blocked: Vec<usize>, // cells neither player may use
+    pub strategy: Strategy,
+    pub seed: Option<u64>,     // fixed seed for the computer's random choices
+    pub group_symmetric: bool, // show one move per set of equivalent moves
+    pub tablebase: Option<String>, // the solver reads the tablebase here, generated if missing
+    pub grade_moves: bool,
+    pub show_eval: bool,   // show the computer's candidate moves and their values
+    pub adaptive: bool,    // adjust the strategy between games to the user's results
+    pub interactive: bool, // a person at a terminal, see detect_environment
+    pub explore: Option<usize>, // print the opening tree this many replies deep and exit
+    pub predict: bool,     // ask the user to guess each computer move
+    pub teach: bool,       // only accept optimal moves and explain the rest
+    pub position: Option<(Board, Cell)>, // start here instead of an empty board
+    pub name: Option<String>, // record results on the leaderboard under this name
+    pub stats_csv: Option<String>, // write one row per game here on quit
+    pub stats_csv_append: bool,
+    pub line_resolution: LineResolution,
+    pub forced_outcomes: bool, // announce when the result with best play changes
+    pub pie: bool,             // the second player may swap sides after the opening move
+    pub prompt_opening: bool,  // wait for Enter before the computer's opening move
+    pub shuffle_difficulty: bool, // pick a random difficulty for each game
+    pub first: Option<Player>, // who moves first in every game; None asks each time
+    pub status_line: bool,     // print a one-line machine-readable status before each turn
+    pub confirm_end: bool,     // wait for Enter after each finished game
+    pub highlight_last: bool,  // mark the most recently played cell
+    pub clock_secs: Option<u64>, // each player's total thinking time per game
+    pub ghost: bool,           // show where the computer would most like to play
+    pub session_autosave: Option<String>, // where to keep the session between games
+    pub autosave_every: usize, // games between session autosaves
+    pub input_pipe: Option<String>, // read input from this file or FIFO instead of stdin
+    pub coords: bool,          // label columns a-c and rows 1-3 around the board
+    pub live_cells: bool,      // mark empty cells no line can be completed through
+    pub daily: bool,           // play the daily challenge
+    pub odds: bool,            // show the odds if both sides played at random from here
+    pub theme: Theme,          // prompts and result messages
+    pub solo: bool,            // the user places both symbols and the computer never plays
+    pub center: bool,          // center the board in the terminal
+    pub padding: usize,        // blank lines around a centered board
+    pub title: Option<String>, // shown above a centered board
+    pub end_when_decided: bool, // stop as soon as no move can change the result
+    pub max_games: Option<usize>, // end the session after this many games
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            layout: Layout::Standard,
+            verbosity: 0,
+            export_svg: None,
+            export_png: None,
+            export_dot: None,
+            dot_depth: 2,
+            think_delay_ms: 0,
+            auto_forced: false,
+            accessible: false,
+            blindfold: false,
+            render_style: RenderStyle::Standard,
+            move_numbers: false,
+            misere: false,
+            stats: false,
+            info: false,
+            blocked: Vec::new(),
+            strategy: DEFAULT_STRATEGY,
+            seed: None,
+            group_symmetric: false,
+            tablebase: None,
+            grade_moves: false,
+            show_eval: false,
+            adaptive: false,
+            interactive: true,
+            explore: None,
+            predict: false,
+            teach: false,
+            position: None,
+            name: None,
+            stats_csv: None,
+            stats_csv_append: false,
+            line_resolution: LineResolution::End,
+            forced_outcomes: false,
+            pie: false,
+            prompt_opening: false,
+            shuffle_difficulty: false,
+            first: None,
+            status_line: false,
+            confirm_end: false,
+            highlight_last: false,
+            clock_secs: None,
+            ghost: false,
+            session_autosave: None,
+            autosave_every: 1,
+            input_pipe: None,
+            coords: false,
+            live_cells: false,
+            daily: false,
+            odds: false,
+            theme: Theme::default(),
+            solo: false,
+            center: false,
+            padding: 1,
+            title: None,
+            end_when_decided: false,
+            max_games: None,
+        }
+    }
+}
+
+impl Config {
+    // Play goes on past completed lines.
+    pub fn arcade(&self) -> bool {
+        self.line_resolution != LineResolution::End
+    }
+
+    pub fn rules(&self) -> Rules {
+        if self.misere {
+            Rules::Misere
+        } else {
+            Rules::Standard
+        }
+    }
+
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut config = Config::default();
+        let mut aggressiveness = DEFAULT_AGGRESSIVENESS;
+        let mut randomize_ties = false;
+        let mut error_rate = DEFAULT_ERROR_RATE;
+        let mut mirror = false;
+        let mut locale = Locale::English;
+        let mut theme_path = None;
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--layout" => {
+                    config.layout = match iter.next().map(|s| s.as_str()) {
+                        Some("standard") => Layout::Standard,
+                        Some("numpad") => Layout::Numpad,
+                        _ => return Err("--layout expects 'standard' or 'numpad'".to_string()),
+                    }
+                }
+                "-v" => config.verbosity = 1,
+                "-vv" => config.verbosity = 2,
+                // Everything at once, not one level up
+                "-vvv" | "--verbose" => config.verbosity = 3,
+                "--mirror" => mirror = true,
+                "--verbosity" => {
+                    config.verbosity = match iter.next().and_then(|s| s.parse().ok()) {
+                        Some(level) if level <= 3 => level,
+                        _ => return Err("--verbosity expects 0 to 3".to_string()),
+                    }
+                }
+                "--auto-forced" => config.auto_forced = true,
+                "--accessible" => config.accessible = true,
+                "--blindfold" => config.blindfold = true,
+                "--unicode" => config.render_style = RenderStyle::Unicode,
+                "--render-style" => {
+                    config.render_style = match iter.next().and_then(|s| RenderStyle::from_name(s))
+                    {
+                        Some(style) => style,
+                        None => {
+                            return Err(
+                                "--render-style expects compact, standard, spacious or unicode"
+                                    .to_string(),
+                            )
+                        }
+                    }
+                }
+                "--move-numbers" => config.move_numbers = true,
+                "--misere" => config.misere = true,
+                "--stats" => config.stats = true,
+                "--info" => config.info = true,
+                "--group-symmetric" => config.group_symmetric = true,
+                "--grade" => config.grade_moves = true,
+                "--show-eval" => config.show_eval = true,
+                "--adaptive" => config.adaptive = true,
+                "--predict" => config.predict = true,
+                "--teach" => config.teach = true,
+                "--arcade" => config.line_resolution = LineResolution::Lock,
+                "--lines" => {
+                    config.line_resolution =
+                        match iter.next().and_then(|s| LineResolution::from_name(s)) {
+                            Some(resolution) => resolution,
+                            None => return Err("--lines expects end, clear or lock".to_string()),
+                        }
+                }
+                "--forced-outcomes" => config.forced_outcomes = true,
+                "--pie" => config.pie = true,
+                "--prompt-opening" => config.prompt_opening = true,
+                "--shuffle-difficulty" => config.shuffle_difficulty = true,
+                "--status-line" => config.status_line = true,
+                "--confirm-end" => config.confirm_end = true,
+                "--highlight-last" => config.highlight_last = true,
+                "--ghost" => config.ghost = true,
+                "--coords" => config.coords = true,
+                "--odds" => config.odds = true,
+                "--daily" => config.daily = true,
+                "--solo" => config.solo = true,
+                "--end-when-decided" => config.end_when_decided = true,
+                "--max-games" => {
+                    config.max_games = match iter.next().and_then(|s| s.parse().ok()) {
+                        Some(games) if games > 0 => Some(games),
+                        _ => return Err("--max-games expects a number of games".to_string()),
+                    }
+                }
+                "--center" => config.center = true,
+                "--padding" => {
+                    config.padding = match iter.next().and_then(|s| s.parse().ok()) {
+                        Some(lines) => lines,
+                        None => return Err("--padding expects a number of lines".to_string()),
+                    }
+                }
+                "--title" => match iter.next() {
+                    Some(title) => config.title = Some(title.clone()),
+                    None => return Err("--title expects some text".to_string()),
+                },
+                "--live-cells" => config.live_cells = true,
+                "--clock" => {
+                    config.clock_secs = match iter.next().and_then(|s| s.parse().ok()) {
+                        Some(secs) if secs > 0 => Some(secs),
+                        _ => return Err("--clock expects a number of seconds".to_string()),
+                    }
+                }
+                "--first" => {
+                    config.first = match iter.next().map(|s| s.as_str()) {
+                        Some("user") => Some(Player::User),
+                        Some("computer") => Some(Player::Computer),
+                        _ => return Err("--first expects 'user' or 'computer'".to_string()),
+                    }
+                }
+                "--blocked" => {
+                    config.blocked = match iter.next().map(|s| parse_cell_list(s)) {
+                        Some(Some(cells)) => cells,
+                        _ => return Err("--blocked expects cells like 0,2,6".to_string()),
+                    }
+                }
+                "--position" => {
+                    let notation = match iter.next() {
+                        Some(notation) => notation,
+                        None => {
+                            return Err(
+                                "--position expects a position like \"ox./.../... o\"".to_string()
+                            )
+                        }
+                    };
+                    let (board, to_move) =
+                        parse_notation(notation).map_err(|e| format!("--position: {}", e))?;
+                    if is_terminal(&board) {
+                        return Err("--position: the game is already over".to_string());
+                    }
+                    config.position = Some((board, to_move));
+                }
+                "--name" => match iter.next() {
+                    Some(name) if !name.is_empty() && !name.contains('\t') => {
+                        config.name = Some(name.clone())
+                    }
+                    _ => return Err("--name expects a player name".to_string()),
+                },
+                "--stats-csv" => match iter.next() {
+                    Some(path) => config.stats_csv = Some(path.clone()),
+                    None => return Err("--stats-csv expects a file path".to_string()),
+                },
+                "--append" => config.stats_csv_append = true,
+                "--input-pipe" => match iter.next() {
+                    Some(path) => config.input_pipe = Some(path.clone()),
+                    None => return Err("--input-pipe expects a file path".to_string()),
+                },
+                "--session-autosave" => match iter.next() {
+                    Some(path) => config.session_autosave = Some(path.clone()),
+                    None => return Err("--session-autosave expects a file path".to_string()),
+                },
+                "--autosave-every" => {
+                    config.autosave_every = match iter.next().and_then(|s| s.parse().ok()) {
+                        Some(games) if games > 0 => games,
+                        _ => return Err("--autosave-every expects a number of games".to_string()),
+                    }
+                }
+                "--tablebase" => match iter.next() {
+                    Some(path) => config.tablebase = Some(path.clone()),
+                    None => return Err("--tablebase expects a file path".to_string()),
+                },
+                "--export-svg" => match iter.next() {
+                    Some(path) => config.export_svg = Some(path.clone()),
+                    None => return Err("--export-svg expects a file path".to_string()),
+                },
+                "--theme" => match iter.next() {
+                    Some(path) => theme_path = Some(path),
+                    None => return Err("--theme expects a file path".to_string()),
+                },
+                "--lang" => {
+                    locale = match iter.next().and_then(|name| Locale::from_name(name)) {
+                        Some(locale) => locale,
+                        None => return Err("--lang expects en or ja".to_string()),
+                    }
+                }
+                "--export-dot" => match iter.next() {
+                    Some(path) => config.export_dot = Some(path.clone()),
+                    None => return Err("--export-dot expects a file path".to_string()),
+                },
+                "--dot-depth" => {
+                    config.dot_depth = match iter.next().and_then(|s| s.parse().ok()) {
+                        Some(depth) if (1..=MAX_DOT_DEPTH).contains(&depth) => depth,
+                        _ => return Err(format!("--dot-depth expects 1 to {}", MAX_DOT_DEPTH)),
+                    }
+                }
+                "--export-png" => match iter.next() {
+                    Some(path) => config.export_png = Some(path.clone()),
+                    None => return Err("--export-png expects a file path".to_string()),
+                },
+                "--think-delay" => {
+                    config.think_delay_ms = match iter.next().and_then(|s| s.parse().ok()) {
+                        Some(ms) => ms,
+                        None => return Err("--think-delay expects milliseconds".to_string()),
+                    }
+                }
+                "--strategy" => {
+                    config.strategy =
+                        match iter.next().map(|s| s.as_str()) {
+                            Some("random") => Strategy::Random,
+                            Some("weighted") => Strategy::WeightedRandom,
+                            Some("heuristic") => Strategy::Heuristic { aggressiveness },
+                            Some("optimal") => Strategy::Optimal { randomize_ties },
+                            Some("human") => Strategy::Human { error_rate },
+                            Some("annoying") => Strategy::Annoying,
+                            Some("exploit") => Strategy::Exploit,
+                            _ => return Err(
+                                "--strategy expects random, weighted, heuristic, optimal, human, annoying or exploit"
+                                    .to_string(),
+                            ),
+                        }
+                }
+                "--randomize-ties" => randomize_ties = true,
+                "--explore" => {
+                    config.explore = match iter.next().and_then(|s| s.parse().ok()) {
+                        Some(depth) => Some(depth),
+                        None => return Err("--explore expects a depth".to_string()),
+                    }
+                }
+                "--seed" => {
+                    config.seed = match iter.next().and_then(|s| s.parse().ok()) {
+                        Some(seed) => Some(seed),
+                        None => return Err("--seed expects a number".to_string()),
+                    }
+                }
+                "--error-rate" => {
+                    error_rate = match iter.next().and_then(|s| s.parse().ok()) {
+                        Some(p) if (0.0..=1.0).contains(&p) => p,
+                        _ => return Err("--error-rate expects 0.0 to 1.0".to_string()),
+                    }
+                }
+                "--aggressiveness" => {
+                    aggressiveness = match iter.next().and_then(|s| s.parse().ok()) {
+                        Some(a) if (0.0..=1.0).contains(&a) => a,
+                        _ => return Err("--aggressiveness expects 0.0 to 1.0".to_string()),
+                    }
+                }
+                _ => return Err(format!("Unknown option: {}", arg)),
+            }
+        }
+        // A theme file rewords the chosen language, whichever came first
+        config.theme = Theme::for_locale(locale);
+        if let Some(path) = theme_path {
+            config.theme = config.theme.load(Path::new(path))?;
+        }
+        if mirror {
+            // Applied last so it combines with --layout in either order
+            config.layout = config.layout.mirrored();
+        }
+        if config.teach {
+            // Both sides play perfectly, so the lesson always ends in a draw
+            config.strategy = Strategy::Optimal { randomize_ties };
+        }
+        if config.solo {
+            // Every move is the user's, so there is nobody to ask about
+            config.first = Some(Player::User);
+        }
+        // Strategy parameters may come before or after --strategy
+        match &mut config.strategy {
+            Strategy::Heuristic { aggressiveness: a } => *a = aggressiveness,
+            Strategy::Optimal { randomize_ties: r } => *r = randomize_ties,
+            Strategy::Human { error_rate: p } => *p = error_rate,
+            Strategy::Random
+            | Strategy::WeightedRandom
+            | Strategy::Annoying
+            | Strategy::Exploit => (),
+        }
+        if !cfg!(feature = "random") {
+            if config.strategy.needs_randomness() {
+                return Err(format!(
+                    "--strategy {} needs a build with the random feature",
+                    config.strategy.name()
+                ));
+            }
+            if config.shuffle_difficulty {
+                return Err(
+                    "--shuffle-difficulty needs a build with the random feature".to_string()
+                );
+            }
+        }
+        if !cfg!(feature = "session") && config.session_autosave.is_some() {
+            return Err("--session-autosave needs a build with the session feature".to_string());
+        }
+        if !cfg!(feature = "png") && config.export_png.is_some() {
+            return Err("--export-png needs a build with the png feature".to_string());
+        }
+        Ok(config)
+    }
+
+    // A difficulty picked between games. An explicit choice replaces any
+    // automatic one.
+    pub fn change_difficulty(&mut self, strategy: Strategy) {
+        self.strategy = strategy;
+        self.adaptive = false;
+        self.shuffle_difficulty = false;
+    }
+
+    // Defaults that depend on where we run. Flags in `explicit_args` (the
+    // command line) win over them.
+    pub fn apply_environment(&mut self, environment: Environment, explicit_args: &[String]) {
+        self.interactive = environment.interactive();
+        if !self.interactive {
+            // Nobody there to press Enter
+            self.confirm_end = false;
+        }
+        if !self.interactive && !explicit_args.iter().any(|arg| arg == "--think-delay") {
+            // An automated run; don't slow it down
+            self.think_delay_ms = 0;
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Environment {
+    pub stdin_is_terminal: bool,
+    pub stdout_is_terminal: bool,
+}
+
+impl Environment {
+    pub fn interactive(self) -> bool {
+        self.stdin_is_terminal && self.stdout_is_terminal
+    }
+}
+
+// Piped input or output (CI, scripts) means nobody is watching the prompts.
+pub fn detect_environment() -> Environment {
+    Environment {
+        stdin_is_terminal: io::stdin().is_terminal(),
+        stdout_is_terminal: io::stdout().is_terminal(),
+    }
+}
+
+// Comma separated internal cell indices, e.g. "0,2,6".
+fn parse_cell_list(s: &str) -> Option<Vec<usize>> {
+    s.split(',')
+        .map(|part| part.trim().parse::<usize>().ok().filter(|&i| i < 9))
+        .collect()
+}
+
+// The config file holds `key = value` lines (a subset of TOML) where each key
+// is a command-line flag without its leading dashes. Flags given on the
+// command line are applied after the file, so they take precedence.
+pub fn config_file_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("sanmoku").join("config.toml"))
+}
+
+// Turns the config file into the equivalent command-line arguments.
+pub fn parse_config_file(text: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim().trim_matches('"')),
+            None => return Err(format!("line {}: expected key = value", n + 1)),
+        };
+        match value {
+            "true" => args.push(format!("--{}", key)),
+            "false" => (),
+            _ => {
+                args.push(format!("--{}", key));
+                args.push(value.to_string());
+            }
+        }
+    }
+    Ok(args)
+}
+
+pub fn default_config_file() -> String {
+    let defaults = Config::default();
+    format!(
+        "# SanmokuRust settings. Command-line flags override these.\n\
+         layout = \"{}\"\n\
+         strategy = \"{}\"\n\
+         aggressiveness = {:.1}\n\
+         think-delay = {}\n\
+         verbosity = {}\n\
+         accessible = {}\n\
+         auto-forced = {}\n\
+         group-symmetric = {}\n\
+         misere = {}\n",
+        defaults.layout.name(),
+        defaults.strategy.name(),
+        DEFAULT_AGGRESSIVENESS,
+        defaults.think_delay_ms,
+        defaults.verbosity,
+        defaults.accessible,
+        defaults.auto_forced,
+        defaults.group_symmetric,
+        defaults.misere,
+    )
+}
+
+// The config file that parse_config_file turns back into `args`.
+pub fn config_file_from_args(args: &[String]) -> String {
+    let mut text = String::from("# SanmokuRust settings. Command-line flags override these.\n");
+    let mut iter = args.iter().peekable();
+    while let Some(arg) = iter.next() {
+        let key = arg.trim_start_matches("--");
+        match iter.peek() {
+            Some(value) if !value.starts_with("--") => {
+                text.push_str(&format!("{} = \"{}\"\n", key, value));
+                iter.next();
+            }
+            _ => text.push_str(&format!("{} = true\n", key)),
+        }
+    }
+    text
+}
+
+pub fn config_file_exists() -> bool {
+    config_file_path().is_some_and(|path| path.exists())
+}
+
+// Arguments from the config file, none when there isn't one. Only the
+// setup wizard creates it. A broken file is reported and ignored.
+pub fn load_config_file() -> Vec<String> {
+    let path = match config_file_path() {
+        Some(path) => path,
+        None => return Vec::new(),
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(_) => return Vec::new(),
+    };
+    let args = parse_config_file(&text).and_then(|args| Config::from_args(&args).map(|_| args));
+    match args {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!(
+                "Warning: ignoring {} ({}), using defaults",
+                path.display(),
+                e
+            );
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUTS: [Layout; 4] = [
+        Layout::Standard,
+        Layout::StandardMirrored,
+        Layout::Numpad,
+        Layout::NumpadMirrored,
+    ];
+
+    #[test]
+    fn numpad_labels_read_like_the_keypad() {
+        let labels: Vec<usize> = (0..9).map(|i| Layout::Numpad.label_of(i)).collect();
+        assert_eq!(labels, vec![7, 8, 9, 4, 5, 6, 1, 2, 3]);
+        assert_eq!(Layout::Numpad.index_of(7), Some(0));
+        assert_eq!(Layout::Numpad.index_of(5), Some(4));
+        assert_eq!(Layout::Numpad.index_of(3), Some(8));
+        assert_eq!(Layout::Numpad.index_of(0), None);
+        assert_eq!(Layout::Standard.index_of(9), None);
+    }
+
+    #[test]
+    fn labels_map_back_to_their_cells() {
+        for layout in LAYOUTS {
+            for i in 0..9 {
+                assert_eq!(layout.index_of(layout.label_of(i)), Some(i), "{:?}", layout);
+            }
+        }
+    }
+
+    #[test]
+    fn mirrored_labels_follow_the_screen() {
+        // Cell 0 is drawn top right, where the numpad has its 9
+        assert_eq!(Layout::NumpadMirrored.label_of(0), 9);
+        assert_eq!(Layout::StandardMirrored.label_of(0), 2);
+        assert_eq!(Layout::NumpadMirrored.index_of(7), Some(2));
+    }
+
+    #[test]
+    fn layout_flag_picks_the_labels() {
+        let args = |list: &[&str]| -> Vec<String> { list.iter().map(|s| s.to_string()).collect() };
+        let config = Config::from_args(&args(&["--layout", "numpad"])).unwrap();
+        assert_eq!(config.layout, Layout::Numpad);
+        let config = Config::from_args(&args(&["--mirror", "--layout", "numpad"])).unwrap();
+        assert_eq!(config.layout, Layout::NumpadMirrored);
+    }
+
+    #[test]
+    fn piped_runs_skip_the_waits_unless_asked() {
+        let args = |list: &[&str]| -> Vec<String> { list.iter().map(|s| s.to_string()).collect() };
+        let piped = Environment {
+            stdin_is_terminal: false,
+            stdout_is_terminal: true,
+        };
+        let cli = args(&["--confirm-end", "--think-delay", "300"]);
+        let mut config = Config::from_args(&cli).unwrap();
+        config.apply_environment(piped, &cli);
+        assert!(!config.interactive);
+        assert!(!config.confirm_end);
+        assert_eq!(config.think_delay_ms, 300);
+
+        // A delay from the config file, not the command line
+        let mut config = Config::from_args(&args(&["--think-delay", "300"])).unwrap();
+        config.apply_environment(piped, &[]);
+        assert_eq!(config.think_delay_ms, 0);
+
+        let terminal = Environment {
+            stdin_is_terminal: true,
+            stdout_is_terminal: true,
+        };
+        let mut config = Config::from_args(&cli).unwrap();
+        config.apply_environment(terminal, &[]);
+        assert!(config.interactive);
+        assert!(config.confirm_end);
+        assert_eq!(config.think_delay_ms, 300);
+    }
+}
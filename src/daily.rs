@@ -0,0 +1,82 @@
+// The daily challenge: a seed taken from the date, so everyone playing on
+// the same day meets the same computer, and a record of the days won.
+use crate::autosave::write_atomically;
+use crate::config::config_file_path;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// Days since 1970-01-01, in UTC.
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / SECONDS_PER_DAY)
+}
+
+// "YYYY-MM-DD" for a day number, by Howard Hinnant's civil_from_days.
+pub fn date_name(day: u64) -> String {
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let d = day_of_year - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = year_of_era + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+// A well-mixed seed for the day (SplitMix64), so neighbouring days don't
+// get similar games.
+pub fn daily_seed(day: u64) -> u64 {
+    let mut z = day.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// Kept next to the config file.
+pub fn daily_path() -> Option<PathBuf> {
+    Some(config_file_path()?.with_file_name("daily.txt"))
+}
+
+// The dates of the challenges won, one per line; a missing file means none.
+pub fn load_days_won(path: &Path) -> io::Result<Vec<String>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text.lines().map(|line| line.trim().to_string()).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn record_day_won(path: &Path, date: &str) -> io::Result<()> {
+    let mut days = load_days_won(path)?;
+    if !days.iter().any(|day| day == date) {
+        days.push(date.to_string());
+    }
+    write_atomically(path, days.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_day_gives_the_same_seed() {
+        assert_eq!(daily_seed(20_740), daily_seed(20_740));
+        assert_ne!(daily_seed(20_740), daily_seed(20_741));
+        assert_ne!(daily_seed(0), daily_seed(1));
+    }
+
+    #[test]
+    fn day_numbers_name_their_dates() {
+        assert_eq!(date_name(0), "1970-01-01");
+        assert_eq!(date_name(11_016), "2000-02-29");
+        assert_eq!(date_name(20_740), "2026-10-14");
+    }
+}
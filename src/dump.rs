@@ -0,0 +1,98 @@
+// Pretty-printed JSON of the whole game state for bug reports, available
+// when built with `--features dump`.
+#[cfg(feature = "dump")]
+use crate::config::Config;
+use crate::model::Model;
+#[cfg(feature = "dump")]
+use crate::model::{Board, Clock, DrawOffer, GameStatus, LineScore, Player, PredictionScore};
+
+// Everything in a Model except the random number generator, which is
+// reseeded from the config on loading.
+#[cfg(feature = "dump")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct State {
+    config: Config,
+    first_player: Option<Player>,
+    board: Board,
+    status: GameStatus,
+    history: Vec<usize>,
+    predictions: PredictionScore,
+    line_score: LineScore,
+    draw_offer: Option<DrawOffer>,
+    swapped: bool,
+    clock: Option<Clock>,
+}
+
+#[cfg(feature = "dump")]
+pub fn dump_state(model: &Model) -> Result<String, String> {
+    let state = State {
+        config: model.config.clone(),
+        first_player: model.first_player,
+        board: model.board,
+        status: model.status,
+        history: model.history.clone(),
+        predictions: model.predictions,
+        line_score: model.line_score,
+        draw_offer: model.draw_offer,
+        swapped: model.swapped,
+        clock: model.clock,
+    };
+    serde_json::to_string_pretty(&state).map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "dump")]
+pub fn load_state(json: &str) -> Result<Model, String> {
+    let state: State = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let mut model = Model {
+        first_player: state.first_player,
+        status: state.status,
+        history: state.history,
+        predictions: state.predictions,
+        line_score: state.line_score,
+        draw_offer: state.draw_offer,
+        swapped: state.swapped,
+        clock: state.clock,
+        ..Model::new(state.config)
+    };
+    model.reset_board(state.board);
+    Ok(model)
+}
+
+#[cfg(not(feature = "dump"))]
+pub fn dump_state(_model: &Model) -> Result<String, String> {
+    Err("built without JSON support".to_string())
+}
+
+#[cfg(all(test, feature = "dump"))]
+mod tests {
+    use super::*;
+    use crate::builder::GameBuilder;
+    use crate::update::play;
+
+    #[test]
+    fn loading_a_dump_gives_the_same_game() {
+        let config = Config {
+            clock_secs: Some(60),
+            ..Config::default()
+        };
+        let model = GameBuilder::from_config(config)
+            .first_player(Player::User)
+            .build()
+            .unwrap();
+        let (mut model, _) = play(model, 4);
+        model.predictions.record(true);
+        model.draw_offer = Some(DrawOffer::Declined);
+        let loaded = load_state(&dump_state(&model).unwrap()).unwrap();
+        assert_eq!(loaded.config, model.config);
+        assert_eq!(loaded.first_player, model.first_player);
+        assert_eq!(loaded.board, model.board);
+        assert_eq!(loaded.available_cells(), model.available_cells());
+        assert_eq!(loaded.status, model.status);
+        assert_eq!(loaded.history, model.history);
+        assert_eq!(loaded.predictions, model.predictions);
+        assert_eq!(loaded.line_score, model.line_score);
+        assert_eq!(loaded.draw_offer, model.draw_offer);
+        assert_eq!(loaded.swapped, model.swapped);
+        assert_eq!(loaded.clock, model.clock);
+    }
+}
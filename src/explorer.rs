@@ -0,0 +1,121 @@
+// Opening explorer: the computer opens optimally, then every essentially
+// different user reply is followed by the computer's optimal counter.
+use crate::config::Layout;
+use crate::helpers::get_available_cells;
+use crate::model::{Board, Cell};
+use crate::positions::is_terminal;
+use crate::rules::Rules;
+use crate::solver::best_move;
+use crate::symmetry::move_orbits;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tree {
+    pub opening: Option<usize>, // None if the board has no free cell
+    pub board: Board,           // after the opening
+    pub replies: Vec<Reply>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    pub cell: usize,
+    pub counter: Option<usize>, // None once the reply ended the game
+    pub board: Board,           // after the reply and its counter
+    pub replies: Vec<Reply>,
+}
+
+const COMPUTER: Cell = Cell::Nought;
+const USER: Cell = Cell::Cross;
+
+// One reply per set of symmetric replies, each explored `depth - 1` further.
+fn replies(board: &Board, depth: usize) -> Vec<Reply> {
+    if depth == 0 || is_terminal(board) {
+        return Vec::new();
+    }
+    move_orbits(board, &get_available_cells(board))
+        .into_iter()
+        .map(|orbit| {
+            let cell = orbit[0];
+            let mut next = *board;
+            next[cell] = USER;
+            let counter = if is_terminal(&next) {
+                None
+            } else {
+                best_move(&next, COMPUTER, Rules::Standard)
+            };
+            if let Some(i) = counter {
+                next[i] = COMPUTER;
+            }
+            Reply {
+                cell,
+                counter,
+                board: next,
+                replies: replies(&next, depth - 1),
+            }
+        })
+        .collect()
+}
+
+// `depth` is the number of user replies to look ahead.
+pub fn explore(depth: usize) -> Tree {
+    let mut board = [Cell::Unfilled; 9];
+    let opening = best_move(&board, COMPUTER, Rules::Standard);
+    if let Some(i) = opening {
+        board[i] = COMPUTER;
+    }
+    Tree {
+        opening,
+        replies: replies(&board, depth),
+        board,
+    }
+}
+
+fn format_replies(replies: &[Reply], layout: Layout, indent: usize, out: &mut String) {
+    for reply in replies {
+        out.push_str(&"  ".repeat(indent));
+        out.push_str(&format!("you {}", layout.label_of(reply.cell)));
+        if let Some(i) = reply.counter {
+            out.push_str(&format!(" -> computer {}", layout.label_of(i)));
+        }
+        out.push('\n');
+        format_replies(&reply.replies, layout, indent + 1, out);
+    }
+}
+
+// Indented listing, one line per reply and its counter.
+pub fn format_tree(tree: &Tree, layout: Layout) -> String {
+    let mut out = match tree.opening {
+        Some(i) => format!("computer {}\n", layout.label_of(i)),
+        None => String::new(),
+    };
+    format_replies(&tree.replies, layout, 1, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symmetry::canonical;
+    use std::collections::HashSet;
+
+    #[test]
+    fn root_has_one_child_per_distinct_reply() {
+        let tree = explore(1);
+        let distinct: HashSet<Board> = get_available_cells(&tree.board)
+            .into_iter()
+            .map(|i| {
+                let mut next = tree.board;
+                next[i] = USER;
+                canonical(&next)
+            })
+            .collect();
+        assert_eq!(tree.replies.len(), distinct.len());
+        assert!(tree.replies.iter().all(|reply| reply.replies.is_empty()));
+    }
+
+    #[test]
+    fn depth_zero_has_no_replies() {
+        let tree = explore(0);
+        assert!(tree.opening.is_some());
+        assert!(tree.replies.is_empty());
+    }
+}
@@ -0,0 +1,391 @@
+use crate::config::Layout;
+use crate::helpers::{get_available_cells, winning_line};
+use crate::model::{Board, Cell};
+use crate::notation::to_notation;
+use crate::positions::is_terminal;
+use crate::rules::Rules;
+use crate::solver::{position_value, Outcome};
+
+const CELL_SIZE: usize = 100;
+
+fn center(i: usize) -> (usize, usize) {
+    (
+        (i % 3) * CELL_SIZE + CELL_SIZE / 2,
+        (i / 3) * CELL_SIZE + CELL_SIZE / 2,
+    )
+}
+
+fn svg_line(x1: usize, y1: usize, x2: usize, y2: usize, style: &str) -> String {
+    format!(
+        "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {}/>\n",
+        x1, y1, x2, y2, style
+    )
+}
+
+// Hand-written SVG of the board: grid lines, circles for noughts and
+// two crossing lines for crosses. A completed line is struck through.
+pub fn to_svg(board: &Board) -> String {
+    let size = CELL_SIZE * 3;
+    let grid = "stroke=\"black\" stroke-width=\"4\"";
+    let glyph = "stroke=\"black\" stroke-width=\"8\" fill=\"none\"";
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">\n",
+        size
+    );
+    for k in 1..3 {
+        svg += &svg_line(k * CELL_SIZE, 0, k * CELL_SIZE, size, grid);
+        svg += &svg_line(0, k * CELL_SIZE, size, k * CELL_SIZE, grid);
+    }
+    let r = CELL_SIZE * 3 / 10;
+    for (i, cell) in board.iter().enumerate() {
+        let (x, y) = center(i);
+        match cell {
+            Cell::Nought => {
+                svg += &format!(
+                    "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" {}/>\n",
+                    x, y, r, glyph
+                );
+            }
+            Cell::Cross => {
+                svg += &svg_line(x - r, y - r, x + r, y + r, glyph);
+                svg += &svg_line(x - r, y + r, x + r, y - r, glyph);
+            }
+            Cell::Blocked => {
+                svg += &format!(
+                    "  <rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"gray\"/>\n",
+                    x - CELL_SIZE / 2 + 4,
+                    y - CELL_SIZE / 2 + 4,
+                    CELL_SIZE - 8
+                );
+            }
+            Cell::Unfilled => (),
+        }
+    }
+    if let Some((a, _, c)) = winning_line(board) {
+        let (x1, y1) = center(a);
+        let (x2, y2) = center(c);
+        svg += &svg_line(
+            x1,
+            y1,
+            x2,
+            y2,
+            "stroke=\"red\" stroke-width=\"10\" stroke-linecap=\"round\"",
+        );
+    }
+    svg += "</svg>\n";
+    svg
+}
+
+// Deeper trees from the empty board run to tens of thousands of nodes.
+pub const MAX_DOT_DEPTH: usize = 4;
+
+// Leaves are filled by who wins with best play from there.
+fn dot_colour(board: &Board, to_move: Cell, rules: Rules) -> &'static str {
+    let winner = match Outcome::from_value(position_value(board, to_move, rules)) {
+        Outcome::Win => to_move,
+        Outcome::Loss => to_move.opponent(),
+        Outcome::Draw => return "lightgray",
+    };
+    match winner {
+        Cell::Nought => "lightblue",
+        _ => "pink",
+    }
+}
+
+// Writes the node for `board` and its subtree, returning its id and
+// whether the depth cut any game short.
+fn dot_node(
+    board: &Board,
+    to_move: Cell,
+    depth: usize,
+    rules: Rules,
+    layout: Layout,
+    next_id: &mut usize,
+    out: &mut String,
+) -> (usize, bool) {
+    let id = *next_id;
+    *next_id += 1;
+    let label = to_notation(board, to_move);
+    let ended = is_terminal(board);
+    if depth == 0 || ended {
+        // A cut-off position is dashed: its colour comes from the solver
+        let style = if ended { "filled" } else { "filled,dashed" };
+        out.push_str(&format!(
+            "  n{} [label=\"{}\", style=\"{}\", fillcolor={}];\n",
+            id,
+            label,
+            style,
+            dot_colour(board, to_move, rules)
+        ));
+        return (id, !ended);
+    }
+    out.push_str(&format!("  n{} [label=\"{}\"];\n", id, label));
+    let mut truncated = false;
+    for i in get_available_cells(board) {
+        let mut next = *board;
+        next[i] = to_move;
+        let (child, cut) = dot_node(
+            &next,
+            to_move.opponent(),
+            depth - 1,
+            rules,
+            layout,
+            next_id,
+            out,
+        );
+        truncated |= cut;
+        out.push_str(&format!(
+            "  n{} -> n{} [label=\"{}\"];\n",
+            id,
+            child,
+            layout.label_of(i)
+        ));
+    }
+    (id, truncated)
+}
+
+// Every line of play from a position, `depth` moves deep (at most
+// MAX_DOT_DEPTH), as a Graphviz digraph with one node per position reached.
+// Also says whether some games went on past the depth.
+pub fn to_dot(
+    board: &Board,
+    to_move: Cell,
+    depth: usize,
+    rules: Rules,
+    layout: Layout,
+) -> (String, bool) {
+    let mut out = String::from("digraph sanmoku {\n  node [shape=box, fontname=monospace];\n");
+    let (_, truncated) = dot_node(
+        board,
+        to_move,
+        depth.min(MAX_DOT_DEPTH),
+        rules,
+        layout,
+        &mut 0,
+        &mut out,
+    );
+    out.push_str("}\n");
+    (out, truncated)
+}
+
+// Distance from (px, py) to the segment from (x1, y1) to (x2, y2).
+#[cfg(feature = "png")]
+fn distance_to_segment(px: f32, py: f32, (x1, y1): (f32, f32), (x2, y2): (f32, f32)) -> f32 {
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let t = (((px - x1) * dx + (py - y1) * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+    let (nx, ny) = (x1 + t * dx - px, y1 + t * dy - py);
+    (nx * nx + ny * ny).sqrt()
+}
+
+// Colour of one pixel of the PNG, drawn like the SVG.
+#[cfg(feature = "png")]
+fn pixel(board: &Board, x: usize, y: usize) -> [u8; 3] {
+    const BLACK: [u8; 3] = [0, 0, 0];
+    let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+    let as_point = |(x, y): (usize, usize)| (x as f32, y as f32);
+    if let Some((a, _, c)) = winning_line(board) {
+        if distance_to_segment(px, py, as_point(center(a)), as_point(center(c))) <= 5.0 {
+            return [255, 0, 0];
+        }
+    }
+    if (1..3).any(|k| {
+        let edge = k * CELL_SIZE;
+        x + 2 >= edge && x < edge + 2 || y + 2 >= edge && y < edge + 2
+    }) {
+        return BLACK;
+    }
+    let i = (y / CELL_SIZE) * 3 + x / CELL_SIZE;
+    let (cx, cy) = as_point(center(i));
+    let r = (CELL_SIZE * 3 / 10) as f32;
+    let on_glyph = match board[i] {
+        Cell::Nought => (((px - cx).powi(2) + (py - cy).powi(2)).sqrt() - r).abs() <= 4.0,
+        Cell::Cross => {
+            distance_to_segment(px, py, (cx - r, cy - r), (cx + r, cy + r)) <= 4.0
+                || distance_to_segment(px, py, (cx - r, cy + r), (cx + r, cy - r)) <= 4.0
+        }
+        Cell::Blocked => {
+            let half = (CELL_SIZE / 2 - 4) as f32;
+            (px - cx).abs() <= half && (py - cy).abs() <= half
+        }
+        Cell::Unfilled => false,
+    };
+    match (on_glyph, board[i]) {
+        (true, Cell::Blocked) => [128, 128, 128],
+        (true, _) => BLACK,
+        (false, _) => [255, 255, 255],
+    }
+}
+
+#[cfg(feature = "png")]
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(feature = "png")]
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// A zlib stream of uncompressed deflate blocks: larger than it could be,
+// but it needs no dependency.
+#[cfg(feature = "png")]
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = data.chunks(0xffff).collect();
+    for (k, block) in blocks.iter().enumerate() {
+        out.push((k + 1 == blocks.len()) as u8);
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+#[cfg(feature = "png")]
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+#[cfg(feature = "png")]
+pub const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// The board as an RGB PNG the size of the SVG, with the same picture.
+#[cfg(feature = "png")]
+pub fn to_png(board: &Board) -> Vec<u8> {
+    let size = CELL_SIZE * 3;
+    let mut raw = Vec::with_capacity(size * (3 * size + 1));
+    for y in 0..size {
+        raw.push(0); // no filter
+        for x in 0..size {
+            raw.extend_from_slice(&pixel(board, x, y));
+        }
+    }
+    let mut header = Vec::new();
+    header.extend_from_slice(&(size as u32).to_be_bytes());
+    header.extend_from_slice(&(size as u32).to_be_bytes());
+    // 8 bits per channel, truecolour, default compression and filters,
+    // not interlaced
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    let mut png = PNG_SIGNATURE.to_vec();
+    png_chunk(&mut png, b"IHDR", &header);
+    png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_notation;
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn checksums_match_known_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn stored_blocks_hold_the_data_as_it_is() {
+        let data = vec![7; 0xffff + 10];
+        let zlib = zlib_stored(&data);
+        // Header, two blocks of five header bytes each, then the checksum
+        assert_eq!(zlib.len(), 2 + 2 * 5 + data.len() + 4);
+        assert_eq!(&zlib[2..7], &[0, 0xff, 0xff, 0, 0]);
+        assert_eq!(zlib[7 + 0xffff], 1);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png_starts_with_the_signature_and_header() {
+        let png = to_png(&[Cell::Unfilled; 9]);
+        assert_eq!(png[..8], PNG_SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        let word = |at: usize| u32::from_be_bytes([png[at], png[at + 1], png[at + 2], png[at + 3]]);
+        assert_eq!(word(8), 13);
+        assert!(word(16) > 0 && word(20) > 0);
+        assert_eq!(word(29), crc32(&png[12..29]));
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+    }
+
+    fn count(svg: &str, element: &str) -> usize {
+        svg.matches(&format!("<{} ", element)).count()
+    }
+
+    #[test]
+    fn svg_draws_one_shape_per_mark() {
+        let (board, _) = parse_notation("ox./.o./x.. o").unwrap();
+        let svg = to_svg(&board);
+        assert_eq!(count(&svg, "circle"), 2);
+        // Four grid lines and two strokes for each cross
+        assert_eq!(count(&svg, "line"), 4 + 2 * 2);
+        assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn svg_strikes_through_the_winning_line() {
+        let (board, _) = parse_notation("ooo/xx./... x").unwrap();
+        let svg = to_svg(&board);
+        assert_eq!(count(&svg, "circle"), 3);
+        assert_eq!(count(&svg, "line"), 4 + 2 * 2 + 1);
+        assert_eq!(svg.matches("stroke=\"red\"").count(), 1);
+    }
+
+    fn dot_of(notation: &str, depth: usize) -> (String, bool) {
+        let (board, to_move) = parse_notation(notation).unwrap();
+        to_dot(&board, to_move, depth, Rules::default(), Layout::Standard)
+    }
+
+    #[test]
+    fn dot_has_one_node_per_position_and_one_edge_per_move() {
+        let (dot, truncated) = dot_of(".../.../... o", 2);
+        assert!(truncated);
+        assert!(dot.starts_with("digraph sanmoku {\n") && dot.ends_with("}\n"));
+        assert_eq!(dot.matches('{').count(), dot.matches('}').count());
+        let nodes = dot
+            .lines()
+            .filter(|l| l.contains("[label=") && !l.contains("->"));
+        assert_eq!(nodes.count(), 1 + 9 + 9 * 8);
+        assert_eq!(dot.matches(" -> ").count(), 9 + 9 * 8);
+        // Deeper requests are cut at MAX_DOT_DEPTH
+        assert_eq!(
+            dot_of(".../.../... o", 9),
+            dot_of(".../.../... o", MAX_DOT_DEPTH)
+        );
+    }
+
+    #[test]
+    fn dot_played_to_the_end_is_not_truncated() {
+        let (dot, truncated) = dot_of("oo./xx./o.. x", MAX_DOT_DEPTH);
+        assert!(!truncated);
+        assert!(!dot.contains("dashed"));
+        assert_eq!(dot.matches('{').count(), dot.matches('}').count());
+    }
+}
@@ -0,0 +1,305 @@
+use crate::model::{Board, Cell};
+
+pub const WIN_LINES: [(usize, usize, usize); 8] = [
+    (0, 1, 2),
+    (3, 4, 5),
+    (6, 7, 8),
+    (0, 3, 6),
+    (1, 4, 7),
+    (2, 5, 8),
+    (0, 4, 8),
+    (2, 4, 6),
+];
+
+pub fn get_available_cells(board: &Board) -> Vec<usize> {
+    let mut availables: Vec<usize> = Vec::new();
+    for (i, cell) in board.iter().enumerate() {
+        if let Cell::Unfilled = cell {
+            availables.push(i)
+        }
+    }
+    availables
+}
+
+pub fn has_bingo(indices: Vec<usize>) -> bool {
+    for (a, b, c) in &WIN_LINES {
+        if indices.contains(a) & indices.contains(b) & indices.contains(c) {
+            return true;
+        }
+    }
+    false
+}
+
+// The first line fully owned by a single symbol, if any. Lines through a
+// blocked cell can never be completed.
+pub fn winning_line(board: &Board) -> Option<(usize, usize, usize)> {
+    WIN_LINES.iter().copied().find(|&(a, b, c)| {
+        matches!(board[a], Cell::Nought | Cell::Cross)
+            && board[a] == board[b]
+            && board[b] == board[c]
+    })
+}
+
+// Cells that would complete a line for `symbol`: two of the line are `symbol`
+// and the third is still unfilled. Pass `symbol.opponent()` to get the cells
+// that have to be blocked.
+pub fn threats(board: &Board, symbol: Cell) -> Vec<usize> {
+    let mut cells = Vec::new();
+    for &(a, b, c) in &WIN_LINES {
+        let line = [a, b, c];
+        let owned = line.iter().filter(|&&i| board[i] == symbol).count();
+        let open = line.iter().find(|&&i| board[i] == Cell::Unfilled);
+        if let (2, Some(&i)) = (owned, open) {
+            if !cells.contains(&i) {
+                cells.push(i);
+            }
+        }
+    }
+    cells.sort_unstable();
+    cells
+}
+
+// Moves that win on the spot for `symbol`, assuming it is their turn.
+// Nothing can be won once the game already has a completed line.
+pub fn winning_moves(board: &Board, symbol: Cell) -> Vec<usize> {
+    if winning_line(board).is_some() {
+        return Vec::new();
+    }
+    threats(board, symbol)
+}
+
+// Whether some line is still open to `symbol`: no opponent or blocked cell
+// on it. Much cheaper than solving, but ignores whose turn it is.
+pub fn can_win(board: &Board, symbol: Cell) -> bool {
+    WIN_LINES.iter().any(|&(a, b, c)| {
+        [a, b, c]
+            .iter()
+            .all(|&i| matches!(board[i], Cell::Unfilled) || board[i] == symbol)
+    })
+}
+
+// Empty cells on at least one line either side could still complete: no
+// blocked cell on it and not both symbols. The others are dead.
+pub fn live_cells(board: &Board) -> Vec<usize> {
+    let mut cells: Vec<usize> = WIN_LINES
+        .iter()
+        .filter(|&&(a, b, c)| {
+            let line = [board[a], board[b], board[c]];
+            let contested = line.contains(&Cell::Nought) && line.contains(&Cell::Cross);
+            !contested && !line.contains(&Cell::Blocked)
+        })
+        .flat_map(|&(a, b, c)| [a, b, c])
+        .filter(|&i| board[i] == Cell::Unfilled)
+        .collect();
+    cells.sort_unstable();
+    cells.dedup();
+    cells
+}
+
+// Playing `cell` leaves `symbol` with two or more ways to win.
+pub fn is_fork(board: &Board, symbol: Cell, cell: usize) -> bool {
+    if board[cell] != Cell::Unfilled {
+        return false;
+    }
+    let mut next = *board;
+    next[cell] = symbol;
+    threats(&next, symbol).len() >= 2
+}
+
+// Every move that gives `symbol` a fork, in cell order. Whether the fork
+// is any good (the opponent might win first) is left to the caller.
+pub fn forks(board: &Board, symbol: Cell) -> Vec<usize> {
+    (0..9).filter(|&i| is_fork(board, symbol, i)).collect()
+}
+
+// Closest cell in `available` that touches `cell`, counting diagonal
+// neighbours; the lowest index wins among equally close cells.
+pub fn nearest_available(cell: usize, available: &[usize]) -> Option<usize> {
+    let distance = |i: usize| {
+        let dr = (i / 3) as i32 - (cell / 3) as i32;
+        let dc = (i % 3) as i32 - (cell % 3) as i32;
+        dr * dr + dc * dc
+    };
+    available
+        .iter()
+        .copied()
+        .filter(|&i| i != cell && distance(i) <= 2)
+        .min_by_key(|&i| (distance(i), i))
+}
+
+// Symbol placed by the k-th move (0-based); noughts always move first.
+pub fn symbol_of_move(k: usize) -> Cell {
+    if k.is_multiple_of(2) {
+        Cell::Nought
+    } else {
+        Cell::Cross
+    }
+}
+
+// Cells where the side to move wins on the spot, then cells where the
+// opponent would, i.e. the ones it has to block. A cell can be in both.
+// Noughts move first, so the marks on the board say whose turn it is.
+pub fn critical_cells(board: &Board) -> (Vec<usize>, Vec<usize>) {
+    let marks = board
+        .iter()
+        .filter(|&&c| matches!(c, Cell::Nought | Cell::Cross))
+        .count();
+    let to_move = symbol_of_move(marks);
+    (
+        winning_moves(board, to_move),
+        winning_moves(board, to_move.opponent()),
+    )
+}
+
+pub fn board_after(start: &Board, moves: &[usize]) -> Board {
+    let mut board = *start;
+    for (k, &i) in moves.iter().enumerate() {
+        board[i] = symbol_of_move(k);
+    }
+    board
+}
+
+// The board as rows, top row first, for code that wants row and column
+// access instead of cell indices.
+pub fn as_grid(board: &Board) -> [[Cell; 3]; 3] {
+    [0, 1, 2].map(|row| [board[row * 3], board[row * 3 + 1], board[row * 3 + 2]])
+}
+
+// The one cell that differs between two boards and its new content, None
+// when they are equal or differ in more than one cell.
+pub fn board_diff(before: &Board, after: &Board) -> Option<(usize, Cell)> {
+    let mut changed = (0..9).filter(|&i| before[i] != after[i]);
+    match (changed.next(), changed.next()) {
+        (Some(i), None) => Some((i, after[i])),
+        _ => None,
+    }
+}
+
+pub fn apply_diff(board: &Board, (i, cell): (usize, Cell)) -> Board {
+    let mut next = *board;
+    next[i] = cell;
+    next
+}
+
+// Takes back a move recorded as a diff.
+pub fn revert_diff(board: &Board, (i, _): (usize, Cell)) -> Board {
+    apply_diff(board, (i, Cell::Unfilled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_notation;
+
+    #[test]
+    fn double_threat_gives_two_winning_cells() {
+        // o has the top row and the left column half done
+        let (board, _) = parse_notation("o.o/.x./o.x x").unwrap();
+        assert_eq!(threats(&board, Cell::Nought), vec![1, 3]);
+        assert_eq!(winning_moves(&board, Cell::Nought), vec![1, 3]);
+        // x has to block, but can only take one of them
+        assert_eq!(threats(&board, Cell::Cross), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn blocked_lines_are_no_threat() {
+        let mut board = [Cell::Unfilled; 9];
+        board[0] = Cell::Nought;
+        board[1] = Cell::Nought;
+        board[2] = Cell::Blocked;
+        assert!(threats(&board, Cell::Nought).is_empty());
+        let (board, _) = parse_notation("oox/.x./... o").unwrap();
+        assert!(threats(&board, Cell::Nought).is_empty());
+        assert_eq!(threats(&board, Cell::Cross), vec![6]);
+    }
+
+    #[test]
+    fn corner_after_the_center_is_a_fork() {
+        // o took a corner and the center, x answered on an edge and the
+        // far corner; the bottom left corner now threatens twice
+        let (board, _) = parse_notation("ox./.o./..x o").unwrap();
+        assert!(is_fork(&board, Cell::Nought, 6));
+        assert!(!is_fork(&board, Cell::Nought, 2));
+        assert!(!is_fork(&board, Cell::Nought, 0));
+        assert!(!is_fork(&board, Cell::Cross, 6));
+    }
+
+    #[test]
+    fn forks_lists_every_forking_cell() {
+        let (board, _) = parse_notation("ox./.o./..x o").unwrap();
+        assert_eq!(forks(&board, Cell::Nought), vec![3, 6]);
+        assert!(forks(&board, Cell::Cross).is_empty());
+        assert!(forks(&[Cell::Unfilled; 9], Cell::Nought).is_empty());
+    }
+
+    #[test]
+    fn critical_cells_show_both_sides_threats() {
+        // o to move: o wins on 2, x would win on 5
+        let (board, _) = parse_notation("oo./xx./... o").unwrap();
+        assert_eq!(critical_cells(&board), (vec![2], vec![5]));
+        // Here 2 completes a line for both sides
+        let (board, _) = parse_notation("oo./..x/..x o").unwrap();
+        assert_eq!(critical_cells(&board), (vec![2], vec![2]));
+    }
+
+    #[test]
+    fn fully_blocked_lines_cannot_be_won() {
+        // Every line runs through a corner of the diagonal or the center
+        let mut board = [Cell::Unfilled; 9];
+        board[0] = Cell::Blocked;
+        board[4] = Cell::Blocked;
+        assert!(can_win(&board, Cell::Nought));
+        board[8] = Cell::Blocked;
+        assert!(!can_win(&board, Cell::Nought));
+        assert!(!can_win(&board, Cell::Cross));
+    }
+
+    #[test]
+    fn nearest_available_prefers_an_edge_neighbour() {
+        // Around a taken center, the lowest edge comes before corners
+        assert_eq!(nearest_available(4, &[0, 2, 5, 7]), Some(5));
+        assert_eq!(nearest_available(4, &[8, 2]), Some(2));
+        // Only touching cells count
+        assert_eq!(nearest_available(0, &[2, 6, 8]), None);
+        assert_eq!(nearest_available(0, &[0, 4]), Some(4));
+    }
+
+    #[test]
+    fn diffs_replay_and_take_back_a_game() {
+        let moves = [4, 0, 8, 2];
+        let mut boards = vec![[Cell::Unfilled; 9]];
+        for k in 1..=moves.len() {
+            boards.push(board_after(&boards[0], &moves[..k]));
+        }
+        let diffs: Vec<(usize, Cell)> = boards
+            .windows(2)
+            .map(|pair| board_diff(&pair[0], &pair[1]).unwrap())
+            .collect();
+        assert_eq!(diffs[0], (4, Cell::Nought));
+        assert_eq!(diffs[3], (2, Cell::Cross));
+        let rebuilt = diffs
+            .iter()
+            .fold(boards[0], |board, &d| apply_diff(&board, d));
+        assert_eq!(rebuilt, boards[4]);
+        let reverted = diffs
+            .iter()
+            .rev()
+            .fold(rebuilt, |board, &d| revert_diff(&board, d));
+        assert_eq!(reverted, boards[0]);
+        // Not one move apart
+        assert_eq!(board_diff(&boards[0], &boards[0]), None);
+        assert_eq!(board_diff(&boards[0], &boards[2]), None);
+    }
+
+    #[test]
+    fn grid_rows_run_top_to_bottom() {
+        let (board, _) = parse_notation("o../.x./..o x").unwrap();
+        let grid = as_grid(&board);
+        assert_eq!(grid[0], [Cell::Nought, Cell::Unfilled, Cell::Unfilled]);
+        assert_eq!(grid[1][1], Cell::Cross);
+        assert_eq!(grid[2][2], Cell::Nought);
+        for i in 0..9 {
+            assert_eq!(grid[i / 3][i % 3], board[i]);
+        }
+    }
+}
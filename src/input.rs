@@ -0,0 +1,181 @@
+// Line input that copes with pastes: whatever one read brings in is split
+// into lines and handed out one per prompt. Lines are decoded only once
+// complete, so a character split between two reads survives.
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+use std::mem;
+use std::thread;
+use std::time::Duration;
+
+const MAX_READ_RETRIES: u32 = 3;
+
+fn decode(line: &[u8]) -> String {
+    String::from_utf8_lossy(line).into_owned()
+}
+
+pub struct InputBuffer {
+    reader: Box<dyn BufRead>,
+    pending: VecDeque<String>, // complete lines not asked for yet
+    partial: Vec<u8>,          // the start of a line still being typed
+}
+
+impl InputBuffer {
+    pub fn new(reader: Box<dyn BufRead>) -> Self {
+        Self {
+            reader,
+            pending: VecDeque::new(),
+            partial: Vec::new(),
+        }
+    }
+
+    // The next line, newline included, or None at the end of input. An
+    // unterminated last line still counts.
+    pub fn next_line(&mut self) -> io::Result<Option<String>> {
+        loop {
+            if let Some(line) = self.pending.pop_front() {
+                return Ok(Some(line));
+            }
+            let chunk = self.read_chunk()?;
+            if chunk.is_empty() {
+                return Ok(if self.partial.is_empty() {
+                    None
+                } else {
+                    Some(decode(&mem::take(&mut self.partial)))
+                });
+            }
+            self.partial.extend_from_slice(&chunk);
+            while let Some(end) = self.partial.iter().position(|&b| b == b'\n') {
+                let rest = self.partial.split_off(end + 1);
+                let line = mem::replace(&mut self.partial, rest);
+                self.pending.push_back(decode(&line));
+            }
+        }
+    }
+
+    // Whatever is available, empty at the end. Interrupted reads are retried
+    // a few times with a growing pause; any other error is passed on.
+    fn read_chunk(&mut self) -> io::Result<Vec<u8>> {
+        let mut retries = 0;
+        loop {
+            match self.reader.fill_buf() {
+                Ok(bytes) => {
+                    let chunk = bytes.to_vec();
+                    self.reader.consume(chunk.len());
+                    return Ok(chunk);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted && retries < MAX_READ_RETRIES => {
+                    retries += 1;
+                    thread::sleep(Duration::from_millis(10 * u64::from(retries)));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+
+    // A reader that fails `failures` times with `kind` before reading `data`.
+    struct Flaky {
+        kind: io::ErrorKind,
+        failures: u32,
+        data: Cursor<Vec<u8>>,
+    }
+
+    impl Flaky {
+        fn boxed(kind: io::ErrorKind, failures: u32, data: &str) -> Box<dyn BufRead> {
+            Box::new(Flaky {
+                kind,
+                failures,
+                data: Cursor::new(data.as_bytes().to_vec()),
+            })
+        }
+    }
+
+    impl Read for Flaky {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.fill_buf()?;
+            self.data.read(buf)
+        }
+    }
+
+    impl BufRead for Flaky {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(io::Error::from(self.kind));
+            }
+            self.data.fill_buf()
+        }
+
+        fn consume(&mut self, amount: usize) {
+            self.data.consume(amount)
+        }
+    }
+
+    #[test]
+    fn interrupted_read_is_retried() {
+        let reader = Flaky::boxed(io::ErrorKind::Interrupted, 1, "4\n");
+        let mut input = InputBuffer::new(reader);
+        assert_eq!(input.next_line().unwrap(), Some("4\n".to_string()));
+        assert_eq!(input.next_line().unwrap(), None);
+    }
+
+    #[test]
+    fn retries_give_up_after_the_cap() {
+        let reader = Flaky::boxed(io::ErrorKind::Interrupted, MAX_READ_RETRIES + 1, "4\n");
+        let error = InputBuffer::new(reader).next_line().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+    }
+
+    #[test]
+    fn other_errors_are_not_retried() {
+        let reader = Flaky::boxed(io::ErrorKind::BrokenPipe, 1, "4\n");
+        let error = InputBuffer::new(reader).next_line().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    // A reader handing out one of `chunks` per read.
+    struct Chunked(VecDeque<Vec<u8>>);
+
+    impl Read for Chunked {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            unimplemented!("InputBuffer only uses fill_buf")
+        }
+    }
+
+    impl BufRead for Chunked {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            Ok(self.0.front().map_or(&[], |chunk| &chunk[..]))
+        }
+
+        fn consume(&mut self, amount: usize) {
+            if amount > 0 {
+                self.0.pop_front();
+            }
+        }
+    }
+
+    #[test]
+    fn character_split_between_reads_is_kept() {
+        let text = "あ\nい".as_bytes();
+        let chunks = vec![text[..1].to_vec(), text[1..5].to_vec(), text[5..].to_vec()];
+        let mut input = InputBuffer::new(Box::new(Chunked(chunks.into())));
+        assert_eq!(input.next_line().unwrap(), Some("あ\n".to_string()));
+        assert_eq!(input.next_line().unwrap(), Some("い".to_string()));
+        assert_eq!(input.next_line().unwrap(), None);
+    }
+
+    #[test]
+    fn pasted_lines_come_out_one_at_a_time() {
+        let reader = Flaky::boxed(io::ErrorKind::Interrupted, 0, "1\n2\n3");
+        let mut input = InputBuffer::new(reader);
+        assert_eq!(input.next_line().unwrap(), Some("1\n".to_string()));
+        assert_eq!(input.next_line().unwrap(), Some("2\n".to_string()));
+        assert_eq!(input.next_line().unwrap(), Some("3".to_string()));
+        assert_eq!(input.next_line().unwrap(), None);
+    }
+}
@@ -0,0 +1,93 @@
+// Consistency checks on a Model, for catching logic bugs early.
+use crate::helpers::get_available_cells;
+use crate::model::{Cell, DrawOffer, GameStatus, Model, Player};
+use crate::rules::{winners, WinCondition};
+use crate::update::{decided_status, resolve_double_win, MAX_ARCADE_MOVES};
+
+fn cells_of(model: &Model, symbol: Cell) -> Vec<usize> {
+    (0..9).filter(|&i| model.board[i] == symbol).collect()
+}
+
+// Describes the first broken invariant, if any.
+pub fn invariant_violation(model: &Model) -> Option<String> {
+    if model.draw_offer == Some(DrawOffer::Accepted) {
+        return if model.status == GameStatus::Draw {
+            None
+        } else {
+            Some(format!("agreed draw but status is {:?}", model.status))
+        };
+    }
+    if model.available_cells() != get_available_cells(&model.board).as_slice() {
+        return Some("the free cells are out of step with the board".to_string());
+    }
+    if model.config.arcade() {
+        // Completed lines are locked away or cleared, so the board no longer
+        // follows from the history
+        let over =
+            !model.board.contains(&Cell::Unfilled) || model.history.len() >= MAX_ARCADE_MOVES;
+        return if over == (model.status == GameStatus::NotFinished) {
+            Some(format!(
+                "status is {:?} after {} moves with free cells left",
+                model.status,
+                model.history.len()
+            ))
+        } else {
+            None
+        };
+    }
+    let noughts = cells_of(model, Cell::Nought);
+    let crosses = cells_of(model, Cell::Cross);
+    // Nought moves first, so it is never behind and at most one move ahead
+    if noughts.len() != crosses.len() && noughts.len() != crosses.len() + 1 {
+        return Some(format!(
+            "{} noughts and {} crosses on the board",
+            noughts.len(),
+            crosses.len()
+        ));
+    }
+    if model.board_after_moves(model.history.len()) != model.board {
+        return Some("the board doesn't match the move history".to_string());
+    }
+    let conditions: Vec<&dyn WinCondition> =
+        model.win_conditions.iter().map(|c| c.as_ref()).collect();
+    let found = winners(&model.board, &conditions);
+    let winner = match found[..] {
+        [] => None,
+        [symbol] => Some(symbol),
+        // Both sides have won; the DoubleWin policy says who, if anyone
+        _ => resolve_double_win(model, &conditions),
+    };
+    let mut expected = match winner {
+        Some(symbol) if symbol == model.user_symbol() => GameStatus::Settled(Player::User),
+        Some(_) => GameStatus::Settled(Player::Computer),
+        None if found.len() > 1 || !model.board.contains(&Cell::Unfilled) => GameStatus::Draw,
+        None => GameStatus::NotFinished,
+    };
+    if expected == GameStatus::NotFinished {
+        if let Some(status) = decided_status(model) {
+            expected = status;
+        }
+    }
+    // Running out of time ends a game the board hasn't decided
+    if let Some(player) = model.clock.and_then(|clock| clock.flagged()) {
+        if expected == GameStatus::NotFinished {
+            expected = GameStatus::Settled(player.other());
+        }
+    }
+    if model.status != expected {
+        return Some(format!(
+            "status is {:?} but the board says {:?}",
+            model.status, expected
+        ));
+    }
+    None
+}
+
+// Panics on a broken invariant in debug builds; does nothing in release.
+pub fn check_invariants(model: &Model) {
+    if cfg!(debug_assertions) {
+        if let Some(problem) = invariant_violation(model) {
+            panic!("model invariant broken: {}", problem);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+// Results per player name, kept across sessions next to the config file.
+// Concurrent runs simply overwrite each other; the last one to quit wins.
+use crate::autosave::write_atomically;
+use crate::config::config_file_path;
+use crate::model::{GameStatus, Player};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const SHOWN_ENTRIES: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Entry {
+    pub name: String,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub streak: u32, // consecutive wins up to the latest game
+}
+
+impl Entry {
+    pub fn new(name: &str) -> Self {
+        Entry {
+            name: name.to_string(),
+            ..Entry::default()
+        }
+    }
+
+    pub fn record(&mut self, status: GameStatus) {
+        match status {
+            GameStatus::Settled(Player::User) => {
+                self.wins += 1;
+                self.streak += 1;
+            }
+            GameStatus::Settled(Player::Computer) => {
+                self.losses += 1;
+                self.streak = 0;
+            }
+            GameStatus::Draw => {
+                self.draws += 1;
+                self.streak = 0;
+            }
+            GameStatus::NotFinished => (),
+        }
+    }
+}
+
+pub fn leaderboard_path() -> Option<PathBuf> {
+    Some(config_file_path()?.with_file_name("leaderboard.txt"))
+}
+
+// One tab separated `name wins losses draws streak` line per player;
+// malformed lines are skipped.
+pub fn parse_leaderboard(text: &str) -> Vec<Entry> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let number = |i: usize| fields.get(i)?.trim().parse::<u32>().ok();
+            match fields.first() {
+                Some(name) if fields.len() == 5 && !name.is_empty() => Some(Entry {
+                    name: name.to_string(),
+                    wins: number(1)?,
+                    losses: number(2)?,
+                    draws: number(3)?,
+                    streak: number(4)?,
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+pub fn format_leaderboard(entries: &[Entry]) -> String {
+    entries
+        .iter()
+        .map(|e| {
+            format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                e.name, e.wins, e.losses, e.draws, e.streak
+            )
+        })
+        .collect()
+}
+
+// A missing file is an empty leaderboard.
+pub fn load_leaderboard(path: &Path) -> io::Result<Vec<Entry>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(parse_leaderboard(&text)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn save_leaderboard(path: &Path, entries: &[Entry]) -> io::Result<()> {
+    write_atomically(path, format_leaderboard(entries))
+}
+
+// Most wins first, then fewest losses, then by name.
+pub fn sort_leaderboard(entries: &mut [Entry]) {
+    entries.sort_by(|a, b| {
+        b.wins
+            .cmp(&a.wins)
+            .then(a.losses.cmp(&b.losses))
+            .then(a.name.cmp(&b.name))
+    });
+}
+
+// The entry for `name`, added if it isn't there yet.
+pub fn entry_for<'a>(entries: &'a mut Vec<Entry>, name: &str) -> &'a mut Entry {
+    match entries.iter().position(|e| e.name == name) {
+        Some(i) => &mut entries[i],
+        None => {
+            entries.push(Entry::new(name));
+            entries.last_mut().unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, wins: u32, losses: u32, draws: u32, streak: u32) -> Entry {
+        Entry {
+            name: name.to_string(),
+            wins,
+            losses,
+            draws,
+            streak,
+        }
+    }
+
+    #[test]
+    fn most_wins_then_fewest_losses_then_name() {
+        let mut entries = vec![
+            entry("dan", 1, 0, 0, 1),
+            entry("bea", 3, 2, 0, 0),
+            entry("cy", 3, 1, 5, 2),
+            entry("al", 3, 1, 0, 0),
+        ];
+        sort_leaderboard(&mut entries);
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["al", "cy", "bea", "dan"]);
+    }
+
+    #[test]
+    fn leaderboard_round_trips_through_text() {
+        let entries = vec![entry("al", 3, 1, 0, 2), entry("山田", 0, 4, 1, 0)];
+        let text = format_leaderboard(&entries);
+        assert_eq!(text, "al\t3\t1\t0\t2\n山田\t0\t4\t1\t0\n");
+        assert_eq!(parse_leaderboard(&text), entries);
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let text = "al\t3\t1\t0\t2\n\t1\t1\t1\t1\nbea\tmany\t0\t0\t0\ncy\t1\t2\n";
+        assert_eq!(parse_leaderboard(text), vec![entry("al", 3, 1, 0, 2)]);
+    }
+
+    #[test]
+    fn recording_keeps_the_win_streak() {
+        let mut e = Entry::new("al");
+        for status in [
+            GameStatus::Settled(Player::User),
+            GameStatus::Settled(Player::User),
+            GameStatus::Draw,
+            GameStatus::Settled(Player::User),
+        ] {
+            e.record(status);
+        }
+        assert_eq!(e, entry("al", 3, 0, 1, 1));
+    }
+}
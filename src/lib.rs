@@ -0,0 +1,33 @@
+pub mod adaptive;
+pub mod autosave;
+pub mod builder;
+pub mod clipboard;
+pub mod collection;
+pub mod commands;
+pub mod commentary;
+pub mod config;
+pub mod daily;
+pub mod dump;
+pub mod explorer;
+pub mod export;
+pub mod helpers;
+pub mod input;
+pub mod invariants;
+pub mod leaderboard;
+pub mod model;
+pub mod notation;
+pub mod positions;
+pub mod puzzles;
+pub mod rng;
+pub mod rules;
+pub mod selfplay;
+pub mod session;
+pub mod solver;
+pub mod strategy;
+pub mod symmetry;
+pub mod tablebase;
+pub mod theme;
+pub mod time;
+pub mod update;
+pub mod version;
+pub mod view;
@@ -1,301 +1,765 @@
-use rand::prelude::*;
-use std::fmt;
-use std::io::stdin;
+use sanmoku::adaptive::{adjust_rating, shuffled_strategy, strategy_for_rating, INITIAL_RATING};
+use sanmoku::autosave::{
+    autosave_path, decode_game, encode_game, install_interrupt_handler, Snapshot,
+};
+use sanmoku::builder::GameBuilder;
+use sanmoku::collection::{collection_path, load_collection};
+use sanmoku::commands::{parse_command, parse_move_log, Command, USAGE};
+use sanmoku::config::{
+    config_file_exists, config_file_from_args, config_file_path, default_config_file,
+    detect_environment, load_config_file, Config, Layout,
+};
+use sanmoku::daily::{daily_path, daily_seed, date_name, load_days_won, record_day_won, today};
+use sanmoku::explorer::{explore, format_tree};
+#[cfg(feature = "png")]
+use sanmoku::export::to_png;
+use sanmoku::export::{to_dot, to_svg};
+use sanmoku::helpers::board_after;
+use sanmoku::leaderboard::{
+    entry_for, leaderboard_path, load_leaderboard, save_leaderboard, sort_leaderboard, Entry,
+    SHOWN_ENTRIES,
+};
+use sanmoku::model::{Cell, GameStatus, Model, Player};
+use sanmoku::notation::parse_notation;
+use sanmoku::positions::{count_positions, tree_size};
+use sanmoku::puzzles::{verify_line, PUZZLES};
+use sanmoku::rng::seeded;
+use sanmoku::rules::Rules;
+use sanmoku::selfplay::{
+    game_winner, match_strategies, play_optimal_games, play_out, SelfPlayStats,
+};
+use sanmoku::session::{ask_unless_capped, csv_rows, GameCap, Session, CSV_HEADER};
+#[cfg(feature = "session")]
+use sanmoku::session::{load_session, save_every};
+use sanmoku::solver::{first_move_outcome, use_tablebase, Outcome};
+use sanmoku::strategy::Strategy;
+use sanmoku::symmetry::reflect_horizontal;
+use sanmoku::tablebase::Tablebase;
+use sanmoku::theme::Theme;
+use sanmoku::time::{RealTime, TimeSource};
+use sanmoku::update::{timed_turn, validate_sequence};
+use sanmoku::version::version_info;
+use sanmoku::view::{
+    analysis_summary, analysis_text, answer_yes_no, ask_play_again, ask_yes_no, first_move_text,
+    opening_equity_text, print_board, read_input_from, replay_frames, rules_text, setup_wizard,
+    solve_puzzle, view, wait_for_enter, PlayAgain,
+};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
-// Models
-#[derive(Debug, Copy, Clone)]
-enum Player {
-    User,
-    Computer,
-}
+const SELFPLAY_GAMES: usize = 100;
 
-#[derive(Debug, Copy, Clone)]
-enum Cell {
-    Nought, // First player
-    Cross,
-    Unfilled,
+fn print_selfplay_stats(stats: &SelfPlayStats) {
+    let percent = |count: usize| 100.0 * count as f64 / stats.games() as f64;
+    println!("{} optimal-vs-optimal games:", stats.games());
+    for (moves, count) in &stats.lengths {
+        println!(
+            "  {} moves: {:3} {}",
+            moves,
+            count,
+            "#".repeat(count * 40 / stats.games())
+        );
+    }
+    println!(
+        "  o wins {:.0}%, x wins {:.0}%, draws {:.0}%",
+        percent(stats.nought_wins),
+        percent(stats.cross_wins),
+        percent(stats.draws)
+    );
 }
 
-impl fmt::Display for Cell {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let c = match self {
-            Cell::Unfilled => ' ',
-            Cell::Nought => 'o',
-            Cell::Cross => 'x',
-        };
-        write!(f, "{}", c)
+// Offers the game saved by the last Ctrl-C. The save is used up once the
+// user resumes it or says no; with nobody to ask it is kept for later.
+fn offer_resume(config: &Config) -> Option<Model> {
+    let path = autosave_path()?;
+    let text = fs::read_to_string(&path).ok()?;
+    let model = decode_game(&text, config.clone())?;
+    if !config.interactive {
+        return None;
+    }
+    let resume = answer_yes_no("Resume the game you interrupted?", &config.theme)?;
+    let _ = fs::remove_file(&path);
+    if resume {
+        Some(model)
+    } else {
+        None
     }
 }
 
-type Board = [Cell; 9];
+// Seeds the game from today's date and returns the date.
+fn start_daily_challenge(config: &mut Config) -> String {
+    let day = today();
+    let date = date_name(day);
+    config.seed = Some(daily_seed(day));
+    println!("Daily challenge for {}", date);
+    let won = daily_path().and_then(|path| load_days_won(&path).ok());
+    if won.is_some_and(|days| days.contains(&date)) {
+        println!("You have already beaten today's challenge.");
+    }
+    date
+}
 
-#[derive(Debug, Copy, Clone)]
-enum GameStatus {
-    Draw,
-    NotFinished,
-    Settled(Player),
+fn record_daily_win(date: &str) {
+    if let Some(path) = daily_path() {
+        if let Err(e) = record_day_won(&path, date) {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+        }
+    }
 }
 
-#[derive(Debug, Copy, Clone)]
-struct Model {
-    first_player: Option<Player>,
-    board: Board,
-    status: GameStatus,
+// Picks up the games of a session that ended without finishing cleanly.
+#[cfg(feature = "session")]
+fn resume_session(config: &Config, session: &mut Session) {
+    let path = match &config.session_autosave {
+        Some(path) => Path::new(path),
+        None => return,
+    };
+    if let Some(saved) = load_session(path) {
+        let question = format!(
+            "Resume the saved session (games played: {})?",
+            saved.games.len()
+        );
+        if config.interactive && ask_yes_no(&question, &config.theme) {
+            session.games = saved.games;
+            session.predictions = saved.predictions;
+        }
+    }
 }
 
-impl Model {
-    fn new() -> Self {
-        Self {
-            status: GameStatus::NotFinished,
-            board: [Cell::Unfilled; 9],
-            first_player: None,
+#[cfg(not(feature = "session"))]
+fn resume_session(_config: &Config, _session: &mut Session) {}
+
+// Saves every `autosave_every` games.
+#[cfg(feature = "session")]
+fn autosave_session(config: &Config, session: &Session) {
+    if let Some(path) = &config.session_autosave {
+        if let Err(e) = save_every(Path::new(path), session, config.autosave_every) {
+            eprintln!("Failed to save {}: {}", path, e);
         }
     }
 }
 
-// Message
-enum Message {
-    CellClicked(usize),
-    PlayerSelected { user_play_first: bool },
-    NoMessage,
+#[cfg(not(feature = "session"))]
+fn autosave_session(_config: &Config, _session: &Session) {}
+
+fn print_leaderboard(entries: &[Entry]) {
+    if entries.is_empty() {
+        return;
+    }
+    println!("Leaderboard:");
+    for (rank, e) in entries.iter().take(SHOWN_ENTRIES).enumerate() {
+        println!(
+            "  {}. {} — {} wins, {} losses, {} draws, streak {}",
+            rank + 1,
+            e.name,
+            e.wins,
+            e.losses,
+            e.draws,
+            e.streak
+        );
+    }
 }
 
-// View
-fn view(model: Model) -> Message {
-    if let GameStatus::Draw = model.status {
-        print_board(&model.board);
-        println!("======== Draw! =======");
-        Message::NoMessage
-    } else if let GameStatus::Settled(player) = model.status {
-        let msg_string = match player {
-            Player::User => "You win, nice!",
-            Player::Computer => "You lose, too bad! Try again!",
-        };
-        print_board(&model.board);
-        println!("======== {} =======", msg_string);
-        Message::NoMessage
-    } else {
-        if model.first_player.is_none() {
-            return select_first_player_view();
+// Adds this session's games to `name`'s entry, re-reading the file so other
+// runs' results since startup are kept.
+fn update_leaderboard(path: &Path, name: &str, session: &Session) {
+    let result = load_leaderboard(path).and_then(|mut entries| {
+        let entry = entry_for(&mut entries, name);
+        for game in &session.games {
+            entry.record(game.status);
         }
-        print_board(&model.board);
-        let x = ask_move(&get_available_cells(&model.board));
-        Message::CellClicked(x)
+        sort_leaderboard(&mut entries);
+        save_leaderboard(path, &entries)
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to update {}: {}", path.display(), e);
     }
 }
 
-fn select_first_player_view() -> Message {
-    let do_user_play_first = ask_user_to_be_first();
-    Message::PlayerSelected {
-        user_play_first: do_user_play_first,
+// Appending only writes the header when the file is new or empty.
+fn write_stats_csv(path: &Path, session: &Session, append: bool) -> io::Result<()> {
+    let existing = if append {
+        fs::read_to_string(path).unwrap_or_default()
+    } else {
+        String::new()
+    };
+    let mut text = existing;
+    if text.is_empty() {
+        text = format!("{}\n", CSV_HEADER);
     }
+    text.push_str(&csv_rows(session));
+    fs::write(path, text)
 }
 
-fn print_board(board: &Board) {
-    let top_row = format!("0|1|2  {}|{}|{}", board[0], board[1], board[2]);
-    let mid_row = format!("3|4|5  {}|{}|{}", board[3], board[4], board[5]);
-    let bot_row = format!("6|7|8  {}|{}|{}", board[6], board[7], board[8]);
-    println!("{}\n{}\n{}", top_row, mid_row, bot_row);
+fn read_file_or_exit(path: &str) -> String {
+    match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            process::exit(1);
+        }
+    }
 }
 
-fn get_user_input() -> Option<String> {
-    let mut input = String::new();
-    match stdin().read_line(&mut input) {
-        Ok(_) => Some(input),
-        Err(_) => None,
+fn analyze(position: &str, rules: Rules, layout: Layout) {
+    match parse_notation(position) {
+        Ok((board, to_move)) => {
+            print_board(&board, layout);
+            println!("{}", analysis_text(&board, to_move, rules, layout));
+        }
+        Err(e) => {
+            eprintln!("{}: {}", position, e);
+            process::exit(1);
+        }
     }
 }
 
-fn ask_user_to_be_first() -> bool {
-    println!("Do you want to play first? [y/n]: ");
-    loop {
-        let ans = get_user_input();
-        if let Some(s) = ans {
-            let first_letter = s.get(0..1);
-            match first_letter {
-                Some("y") => return true,
-                Some("n") => return false,
-                _ => println!("Please input 'y' or 'n' :"),
+// Set once the user presses Enter, for skipping an animation to its end.
+fn skip_on_enter() -> Arc<AtomicBool> {
+    let skip = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&skip);
+    thread::spawn(move || {
+        if let Ok(1..) = io::stdin().read_line(&mut String::new()) {
+            flag.store(true, Ordering::Relaxed);
+        }
+    });
+    skip
+}
+
+fn replay(path: &str, goto: Option<usize>, animate: Option<u64>, layout: Layout) {
+    let moves = match parse_move_log(&read_file_or_exit(path)) {
+        Some(moves) => moves,
+        None => {
+            eprintln!("{}: expected cells 0-8 separated by spaces or commas", path);
+            process::exit(1);
+        }
+    };
+    if let Err((k, e)) = validate_sequence(Player::User, &moves) {
+        eprintln!(
+            "Move {}: cell {} can't be played: {}",
+            k + 1,
+            layout.label_of(moves[k]),
+            e
+        );
+        process::exit(1);
+    }
+    if let Some(n) = goto {
+        if n > moves.len() {
+            eprintln!("--goto expects a move number from 0 to {}", moves.len());
+            process::exit(1);
+        }
+        println!("After move {}:", n);
+        print_board(&board_after(&[Cell::Unfilled; 9], &moves[..n]), layout);
+        return;
+    }
+    let skip = animate.map(|_| skip_on_enter());
+    let frames = replay_frames(&moves, layout);
+    for (k, frame) in frames.iter().enumerate() {
+        let last = k + 1 == frames.len();
+        if let Some(skip) = &skip {
+            if skip.load(Ordering::Relaxed) && !last {
+                continue;
             }
+            // Clear the screen and go back to its top
+            print!("\x1b[2J\x1b[H");
+        }
+        print!("{}", frame);
+        if let (Some(delay), false) = (animate, last) {
+            println!("Press Enter to skip to the end");
+            thread::sleep(Duration::from_millis(delay));
         }
     }
 }
 
-fn ask_move(available: &Vec<usize>) -> usize {
-    println!("What's your move? [0-8]: ");
-    loop {
-        let ans = get_user_input();
-        if let Some(s) = ans {
-            let ans = s.get(0..1).and_then(|s| s.parse::<usize>().ok());
-            if let Some(i) = ans {
-                if available.contains(&i) {
-                    return i;
-                } else {
-                    println!("The cell {:?} is not available", i)
-                }
+fn batch(path: &str, rules: Rules, layout: Layout) {
+    for line in read_file_or_exit(path).lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_notation(line) {
+            Ok((board, to_move)) => {
+                println!(
+                    "{}: {}",
+                    line,
+                    analysis_summary(&board, to_move, rules, layout)
+                )
             }
+            Err(e) => println!("{}: {}", line, e),
         }
-        println!("Please input [0-8] :")
     }
 }
 
-// Update
-fn update(model: Model, message: Message) -> Model {
-    match message {
-        Message::CellClicked(selected_cell) => update_board(model, selected_cell),
-        Message::PlayerSelected {
-            user_play_first: flag,
-        } => update_player_selection(model, flag),
-        Message::NoMessage => model,
+fn puzzles(number: Option<usize>, layout: Layout) {
+    match number {
+        None => {
+            for (n, puzzle) in PUZZLES.iter().enumerate() {
+                println!("{}. {} ({})", n + 1, puzzle.title, puzzle.position);
+            }
+        }
+        Some(n) => match n.checked_sub(1).and_then(|i| PUZZLES.get(i)) {
+            Some(puzzle) => solve_puzzle(puzzle, layout),
+            None => {
+                eprintln!("There are puzzles 1 to {}", PUZZLES.len());
+                process::exit(2);
+            }
+        },
     }
 }
 
-fn update_player_selection(model: Model, is_player_first: bool) -> Model {
-    if is_player_first {
-        Model {
-            first_player: Some(Player::User),
-            ..model
+fn collections(number: Option<usize>, layout: Layout) {
+    let bookmarks = match collection_path().map(|path| load_collection(&path)) {
+        Some(Ok(bookmarks)) => bookmarks,
+        Some(Err(e)) => {
+            eprintln!("Failed to read the collection: {}", e);
+            process::exit(1);
         }
-    } else {
-        let new_model = Model {
-            first_player: Some(Player::Computer),
-            ..model
-        };
-        update_board_with_random_play_computer_move(new_model)
+        None => Vec::new(),
+    };
+    match number {
+        None if bookmarks.is_empty() => {
+            println!("No bookmarked positions yet; type b <label> during a game to add one")
+        }
+        None => {
+            for (n, bookmark) in bookmarks.iter().enumerate() {
+                println!("{}. {} ({})", n + 1, bookmark.label, bookmark.notation());
+            }
+        }
+        Some(n) => match n.checked_sub(1).and_then(|i| bookmarks.get(i)) {
+            Some(bookmark) => {
+                println!("{}", bookmark.label);
+                analyze(&bookmark.notation(), Rules::Standard, layout);
+            }
+            None => {
+                eprintln!("There are {} bookmarked positions", bookmarks.len());
+                process::exit(2);
+            }
+        },
     }
 }
 
-fn update_board_helper(board: &Board, selected_index: usize, cell: Cell) -> Board {
-    let mut new_board = [Cell::Unfilled; 9];
-    for i in 0..9 {
-        if i == selected_index {
-            new_board[i] = cell
-        } else {
-            new_board[i] = board[i]
+fn verify(position: &str, line: &str, claimed: Option<Outcome>, rules: Rules, layout: Layout) {
+    let (board, to_move) = match parse_notation(position) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}: {}", position, e);
+            process::exit(1);
+        }
+    };
+    let cells: Option<Vec<usize>> = line
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse().ok().and_then(|label| layout.index_of(label)))
+        .collect();
+    let cells = match cells {
+        Some(cells) => cells,
+        None => {
+            eprintln!(
+                "{}: expected cell labels separated by spaces or commas",
+                line
+            );
+            process::exit(1);
+        }
+    };
+    match verify_line(&board, to_move, &cells, claimed, rules) {
+        Ok(outcome) => println!("The line holds: {} for {}", outcome.name(), to_move),
+        Err(e) => {
+            println!("The line fails: {}", e);
+            process::exit(1);
         }
     }
-    new_board
 }
 
-fn update_board(model: Model, selected_cell: usize) -> Model {
-    update_board_with_random_play_computer_move(update_board_with_user_move(model, selected_cell))
+fn match_strategies_table(
+    first: Strategy,
+    second: Strategy,
+    games: usize,
+    seed: u64,
+    rules: Rules,
+) {
+    let results = match_strategies(first, second, games, seed, rules);
+    if first.is_deterministic() && second.is_deterministic() {
+        println!("Both strategies are deterministic: only who starts changes between games.");
+    }
+    println!(
+        "{:<12} {:>5} {:>5} {:>5}",
+        "Strategy", "Won", "Lost", "Drawn"
+    );
+    for (strategy, won, lost) in [
+        (first, results.wins, results.losses),
+        (second, results.losses, results.wins),
+    ] {
+        println!(
+            "{:<12} {:>5} {:>5} {:>5}",
+            strategy.name(),
+            won,
+            lost,
+            results.draws
+        );
+    }
 }
 
-fn update_board_with_user_move(model: Model, selected_cell: usize) -> Model {
-    let user_cell_type = if let Some(Player::User) = model.first_player {
-        Cell::Nought
-    } else {
-        Cell::Cross
-    };
-    let new_board = update_board_helper(&model.board, selected_cell, user_cell_type);
-    let new_model = Model {
-        board: new_board,
-        ..model
+fn simulate(
+    position: &str,
+    nought: Strategy,
+    cross: Strategy,
+    seed: Option<u64>,
+    rules: Rules,
+    layout: Layout,
+) {
+    let (mut board, to_move) = match parse_notation(position) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}: {}", position, e);
+            process::exit(1);
+        }
     };
-    update_game_status(new_model)
+    let moves = play_out(&mut board, to_move, nought, cross, rules, &mut seeded(seed));
+    let labels: Vec<String> = moves
+        .iter()
+        .map(|&i| layout.label_of(i).to_string())
+        .collect();
+    println!(
+        "{} ({}) against {} ({})",
+        nought.name(),
+        Cell::Nought,
+        cross.name(),
+        Cell::Cross
+    );
+    println!("Moves: {}", labels.join(" "));
+    print_board(&board, layout);
+    match game_winner(&board, rules) {
+        Some(winner) => println!("{} wins", winner),
+        None => println!("Draw"),
+    }
 }
 
-fn update_board_with_random_play_computer_move(model: Model) -> Model {
-    let mut availables = get_available_cells(&model.board);
-    let mut rng = rand::thread_rng();
-    availables.shuffle(&mut rng);
-    let computer_cell_type = if let Some(Player::User) = model.first_player {
-        Cell::Cross
-    } else {
-        Cell::Nought
+fn main() {
+    let cli_args: Vec<String> = env::args().skip(1).collect();
+    match parse_command(&cli_args) {
+        Ok(Command::Version) => println!("{}", version_info()),
+        Ok(Command::Play(args)) => play(args),
+        Ok(Command::Daily(mut args)) => {
+            args.push("--daily".to_string());
+            play(args)
+        }
+        Ok(Command::Analyze {
+            position,
+            layout,
+            rules,
+        }) => analyze(&position, rules, layout),
+        Ok(Command::Replay {
+            path,
+            goto,
+            animate,
+            layout,
+        }) => replay(&path, goto, animate, layout),
+        Ok(Command::Batch {
+            path,
+            layout,
+            rules,
+        }) => batch(&path, rules, layout),
+        Ok(Command::Puzzles { number, layout }) => puzzles(number, layout),
+        Ok(Command::Collections { number, layout }) => collections(number, layout),
+        Ok(Command::Verify {
+            position,
+            line,
+            outcome,
+            layout,
+            rules,
+        }) => verify(&position, &line, outcome, rules, layout),
+        Ok(Command::MatchStrategies {
+            first,
+            second,
+            games,
+            seed,
+            rules,
+        }) => match_strategies_table(first, second, games, seed, rules),
+        Ok(Command::Simulate {
+            position,
+            nought,
+            cross,
+            seed,
+            layout,
+            rules,
+        }) => simulate(&position, nought, cross, seed, rules, layout),
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+// First launch at a terminal: offer the setup wizard and keep its answers
+// if the user wants them saved.
+fn run_wizard() {
+    let args = setup_wizard();
+    let path = match config_file_path() {
+        Some(path)
+            if ask_yes_no(
+                &format!("Save these settings to {}?", path.display()),
+                &Theme::default(),
+            ) =>
+        {
+            path
+        }
+        _ => return,
     };
-    if let Some(i) = availables.pop() {
-        let new_board = update_board_helper(&model.board, i, computer_cell_type);
-        let new_model = Model {
-            board: new_board,
-            ..model
-        };
-        update_game_status(new_model)
+    // Keeping every default still leaves a file listing them to edit
+    let text = if args.is_empty() {
+        default_config_file()
     } else {
-        update_game_status(model)
+        config_file_from_args(&args)
+    };
+    let written = fs::create_dir_all(path.parent().unwrap()).and_then(|_| fs::write(&path, text));
+    if let Err(e) = written {
+        eprintln!("Failed to write {}: {}", path.display(), e);
     }
 }
 
-fn update_game_status(model: Model) -> Model {
-    let mut noughts = Vec::new();
-    let mut crosses = Vec::new();
-    let mut num_unfilled = 0;
-    for i in 0..9 {
-        if let Cell::Nought = model.board[i] {
-            noughts.push(i);
-        } else if let Cell::Cross = model.board[i] {
-            crosses.push(i);
-        } else {
-            num_unfilled += 1;
+fn play(cli_args: Vec<String>) {
+    // Flags or an existing config file mean the user has already chosen
+    if cli_args.is_empty() && !config_file_exists() && detect_environment().interactive() {
+        run_wizard();
+    }
+    let mut args = load_config_file();
+    args.extend(cli_args.iter().cloned());
+    let mut config = match Config::from_args(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+    if let Some(path) = &config.input_pipe {
+        if let Err(e) = read_input_from(Path::new(path)) {
+            eprintln!("Failed to open {}: {}", path, e);
+            process::exit(1);
         }
     }
-    if has_bingo(noughts) {
-        return Model {
-            status: GameStatus::Settled(if let Some(Player::User) = model.first_player {
-                Player::User
-            } else {
-                Player::Computer
-            }),
-            ..model
-        };
+    if let Some(path) = &config.tablebase {
+        match Tablebase::load_or_build(Path::new(path)) {
+            Ok(tablebase) => {
+                println!("Tablebase {}: {} positions", path, tablebase.len());
+                use_tablebase(tablebase);
+            }
+            Err(e) => {
+                eprintln!("Failed to load {}: {}", path, e);
+                process::exit(1);
+            }
+        }
     }
-    if has_bingo(crosses) {
-        return Model {
-            status: GameStatus::Settled(if let Some(Player::User) = model.first_player {
-                Player::Computer
-            } else {
-                Player::User
-            }),
-            ..model
-        };
+    if config.stats {
+        println!("Reachable positions: {}", count_positions(false));
+        println!("Distinct up to symmetry: {}", count_positions(true));
+        println!(
+            "Game tree size: {} positions",
+            tree_size(&[Cell::Unfilled; 9], Cell::Nought)
+        );
+        println!("{}", first_move_text(first_move_outcome()));
+        println!("{}", opening_equity_text(config.layout));
+        print_selfplay_stats(&play_optimal_games(
+            SELFPLAY_GAMES,
+            config.seed.unwrap_or(0),
+            config.rules(),
+        ));
+        return;
+    }
+    if let Some(path) = &config.export_dot {
+        let to_move = config.position.map_or(Cell::Nought, |(_, to_move)| to_move);
+        let board = Model::initial_board(&config);
+        let (dot, truncated) = to_dot(
+            &board,
+            to_move,
+            config.dot_depth,
+            config.rules(),
+            config.layout,
+        );
+        if truncated {
+            eprintln!(
+                "Warning: some games go on past --dot-depth {}",
+                config.dot_depth
+            );
+        }
+        if let Err(e) = fs::write(path, dot) {
+            eprintln!("Failed to write {}: {}", path, e);
+            process::exit(1);
+        }
+        return;
     }
-    if num_unfilled == 0 {
-        return Model {
-            status: GameStatus::Draw,
-            ..model
+    if let Some(depth) = config.explore {
+        print!("{}", format_tree(&explore(depth), config.layout));
+        return;
+    }
+    if config.info {
+        // As the first game will be set up, without playing the opening
+        let first = config.first;
+        let mut model = Model::new(config);
+        model.first_player = first;
+        println!("{}", rules_text(&model));
+        return;
+    }
+    config.apply_environment(detect_environment(), &cli_args);
+    let challenge = if config.daily {
+        Some(start_daily_challenge(&mut config))
+    } else {
+        None
+    };
+    let mut session = Session::new(config.clone());
+    resume_session(&config, &mut session);
+    let mut rating = INITIAL_RATING;
+    let leaderboard = leaderboard_path();
+    if let Some(path) = &leaderboard {
+        if let Ok(mut entries) = load_leaderboard(path) {
+            sort_leaderboard(&mut entries);
+            print_leaderboard(&entries);
+        }
+    }
+    let snapshot: Snapshot = Arc::new(Mutex::new(None));
+    let mut resumed = if install_interrupt_handler(Arc::clone(&snapshot)).is_ok() {
+        offer_resume(&config)
+    } else {
+        None
+    };
+    // Its own generator, so the games' seeds stay what they were without
+    // the shuffle
+    let mut difficulty_rng = seeded(config.seed);
+    let time = RealTime;
+    let mut cap = GameCap::new(config.max_games);
+    let mut first_game = true;
+    'games: loop {
+        let mut game_config = config.clone();
+        // Vary a fixed seed per game so replays of a session match, but
+        // consecutive games don't
+        game_config.seed = config
+            .seed
+            .map(|seed| seed.wrapping_add(session.games.len() as u64));
+        if config.shuffle_difficulty {
+            game_config.strategy = shuffled_strategy(&mut difficulty_rng);
+            println!("Difficulty: {}", game_config.strategy.name());
+        } else if config.adaptive {
+            game_config.strategy = strategy_for_rating(rating);
+            println!("Difficulty: {}", game_config.strategy.name());
+        }
+        let built = match resumed.take() {
+            Some(model) => Ok(model),
+            None => {
+                let builder = GameBuilder::from_config(game_config);
+                match config.first {
+                    Some(player) => builder.first_player(player),
+                    None => builder,
+                }
+                .build()
+            }
         };
+        let started = time.now();
+        let mut model = match built {
+            Ok(model) => model,
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(2);
+            }
+        };
+        while let GameStatus::NotFinished = model.status {
+            model = timed_turn(model, &time, view);
+            *snapshot.lock().unwrap() = match model.status {
+                GameStatus::NotFinished => encode_game(&model),
+                _ => None,
+            };
+        }
+        view(&model);
+        let exported = if model.config.layout.is_mirrored() {
+            reflect_horizontal(&model.board)
+        } else {
+            model.board
+        };
+        if let Some(path) = &model.config.export_svg {
+            if let Err(e) = fs::write(path, to_svg(&exported)) {
+                eprintln!("Failed to write {}: {}", path, e);
+            }
+        }
+        #[cfg(feature = "png")]
+        if let Some(path) = &model.config.export_png {
+            if let Err(e) = fs::write(path, to_png(&exported)) {
+                eprintln!("Failed to write {}: {}", path, e);
+            }
+        }
+        // Solo boards are practice, not results
+        if !config.solo {
+            session.record(&model, time.since(started));
+            autosave_session(&config, &session);
+            rating = adjust_rating(rating, model.status);
+        }
+        // Only the first game of this run is the challenge, whatever a
+        // resumed session already holds
+        if let (Some(date), true) = (&challenge, first_game) {
+            if model.status == GameStatus::Settled(Player::User) {
+                println!("You beat the challenge for {}!", date);
+                record_daily_win(date);
+            }
+        }
+        first_game = false;
+        if let Some(accuracy) = session.predictions.accuracy() {
+            println!(
+                "Predictions: {}/{} correct ({:.0}%)",
+                session.predictions.correct,
+                session.predictions.made,
+                100.0 * accuracy
+            );
+        }
+        // Nobody is left to answer once input has ended
+        if config.confirm_end && !wait_for_enter() {
+            break 'games;
+        }
+        // The last game allowed ends the session without asking
+        let again = ask_unless_capped(&mut cap, || loop {
+            match ask_play_again(&config.theme) {
+                PlayAgain::Yes => break true,
+                PlayAgain::No => break false,
+                PlayAgain::ChangeDifficulty(strategy) => {
+                    config.change_difficulty(strategy);
+                    println!("Difficulty set to {}.", strategy.name());
+                }
+            }
+        });
+        match again {
+            Some(true) => (),
+            Some(false) => break 'games,
+            None => {
+                let r = session.results();
+                println!(
+                    "That was the last game: {} won, {} lost, {} drawn.",
+                    r.wins, r.losses, r.draws
+                );
+                break 'games;
+            }
+        }
     }
-    model
-}
-
-// Helpers
-fn get_available_cells(board: &Board) -> Vec<usize> {
-    let mut availables: Vec<usize> = Vec::new();
-    for i in 0..9 {
-        if match board[i] {
-            Cell::Unfilled => true,
-            _ => false,
-        } {
-            availables.push(i)
-        }
-    }
-    availables
-}
-
-fn has_bingo(indices: Vec<usize>) -> bool {
-    let pattern = [
-        (0, 1, 2),
-        (3, 4, 5),
-        (6, 7, 8),
-        (0, 3, 6),
-        (1, 4, 7),
-        (2, 5, 8),
-        (0, 4, 8),
-        (2, 4, 6),
-    ];
-    for (a, b, c) in &pattern {
-        if indices.contains(&a) & indices.contains(&b) & indices.contains(&c) {
-            return true;
-        }
-    }
-    false
-}
-
-fn main() {
-    let mut model = Model::new();
-    while let GameStatus::NotFinished = model.status {
-        let msg = view(model);
-        model = update(model, msg);
+    if config.shuffle_difficulty {
+        for (name, r) in session.results_by_strategy() {
+            println!(
+                "Against {}: {} won, {} lost, {} drawn",
+                name, r.wins, r.losses, r.draws
+            );
+        }
+    }
+    if let Some(path) = &config.stats_csv {
+        if let Err(e) = write_stats_csv(Path::new(path), &session, config.stats_csv_append) {
+            eprintln!("Failed to write {}: {}", path, e);
+        }
+    }
+    if let (Some(path), Some(name)) = (&leaderboard, &config.name) {
+        update_leaderboard(path, name, &session);
+    }
+    // Only a session that didn't get this far needs resuming
+    if let Some(path) = &config.session_autosave {
+        let _ = fs::remove_file(path);
     }
-    view(model);
 }
@@ -0,0 +1,436 @@
+use crate::config::Config;
+use crate::helpers::get_available_cells;
+use crate::rng::{seeded, GameRng};
+use crate::rules::{default_conditions, DoubleWin, WinCondition};
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Player {
+    User,
+    Computer,
+}
+
+impl Player {
+    pub fn other(self) -> Player {
+        match self {
+            Player::User => Player::Computer,
+            Player::Computer => Player::User,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Cell {
+    Nought, // First player
+    Cross,
+    Unfilled,
+    Blocked, // Permanently unusable by either player
+}
+
+impl Cell {
+    pub fn opponent(self) -> Cell {
+        match self {
+            Cell::Nought => Cell::Cross,
+            Cell::Cross => Cell::Nought,
+            Cell::Unfilled => Cell::Unfilled,
+            Cell::Blocked => Cell::Blocked,
+        }
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let c = match self {
+            Cell::Unfilled => ' ',
+            Cell::Blocked => '#',
+            Cell::Nought => 'o',
+            Cell::Cross => 'x',
+        };
+        write!(f, "{}", c)
+    }
+}
+
+pub type Board = [Cell; 9];
+
+// Two bits per cell, first cell in the highest bits, so a board fits in the
+// low 18 bits of a u32.
+pub trait PackedBoard: Sized {
+    fn pack(&self) -> u32;
+    // None if bits above the 18th are set.
+    fn unpack(bits: u32) -> Option<Self>;
+}
+
+impl PackedBoard for Board {
+    fn pack(&self) -> u32 {
+        self.iter().fold(0, |bits, cell| {
+            bits << 2
+                | match cell {
+                    Cell::Unfilled => 0,
+                    Cell::Nought => 1,
+                    Cell::Cross => 2,
+                    Cell::Blocked => 3,
+                }
+        })
+    }
+
+    fn unpack(bits: u32) -> Option<Self> {
+        if bits >> 18 != 0 {
+            return None;
+        }
+        let mut board = [Cell::Unfilled; 9];
+        for (i, cell) in board.iter_mut().enumerate() {
+            *cell = match (bits >> (2 * (8 - i))) & 0b11 {
+                0 => Cell::Unfilled,
+                1 => Cell::Nought,
+                2 => Cell::Cross,
+                _ => Cell::Blocked,
+            };
+        }
+        Some(board)
+    }
+}
+
+// Building a board from input of any length. The standard library's
+// `Board::try_from(cells)` works as well (TryFrom can't be reimplemented
+// for an array), but this error says what the length was.
+pub trait BoardFromCells: Sized {
+    fn from_cells(cells: &[Cell]) -> Result<Self, WrongLength>;
+}
+
+#[derive(Debug, PartialEq)]
+pub struct WrongLength(pub usize);
+
+impl fmt::Display for WrongLength {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a board has 9 cells, got {}", self.0)
+    }
+}
+
+impl BoardFromCells for Board {
+    fn from_cells(cells: &[Cell]) -> Result<Self, WrongLength> {
+        Board::try_from(cells).map_err(|_| WrongLength(cells.len()))
+    }
+}
+
+// A draw offered by one side and what became of it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrawOffer {
+    Pending(Player), // made by this player, waiting for an answer
+    Accepted,        // the game ended in an agreed draw
+    Declined,
+}
+
+// Lines completed by each player in arcade mode.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineScore {
+    pub user: u32,
+    pub computer: u32,
+}
+
+// How often the user guessed the computer's move under --predict.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PredictionScore {
+    pub made: u32,
+    pub correct: u32,
+}
+
+impl PredictionScore {
+    pub fn record(&mut self, correct: bool) {
+        self.made += 1;
+        if correct {
+            self.correct += 1;
+        }
+    }
+
+    pub fn add(&mut self, other: PredictionScore) {
+        self.made += other.made;
+        self.correct += other.correct;
+    }
+
+    // Fraction of correct predictions, None before the first one.
+    pub fn accuracy(self) -> Option<f64> {
+        if self.made == 0 {
+            None
+        } else {
+            Some(self.correct as f64 / self.made as f64)
+        }
+    }
+}
+
+// Time left for each player under --clock, like a chess clock.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Clock {
+    pub user: Duration,
+    pub computer: Duration,
+}
+
+impl Clock {
+    pub fn new(budget: Duration) -> Self {
+        Clock {
+            user: budget,
+            computer: budget,
+        }
+    }
+
+    pub fn remaining(&self, player: Player) -> Duration {
+        match player {
+            Player::User => self.user,
+            Player::Computer => self.computer,
+        }
+    }
+
+    pub fn charge(&mut self, player: Player, spent: Duration) {
+        let left = match player {
+            Player::User => &mut self.user,
+            Player::Computer => &mut self.computer,
+        };
+        *left = left.saturating_sub(spent);
+    }
+
+    // The player whose time has run out, if any.
+    pub fn flagged(&self) -> Option<Player> {
+        [Player::User, Player::Computer]
+            .iter()
+            .copied()
+            .find(|&player| self.remaining(player) == Duration::ZERO)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameStatus {
+    Draw,
+    NotFinished,
+    Settled(Player),
+}
+
+#[derive(Debug, Clone)]
+pub struct Model {
+    pub rng: GameRng, // Seeded from the config so games can be reproduced
+    pub config: Config,
+    pub first_player: Option<Player>,
+    pub board: Board,
+    pub(crate) available: Vec<usize>, // the unfilled cells, kept in step with `board`
+    pub status: GameStatus,
+    pub history: Vec<usize>, // Cells in the order they were played
+    pub predictions: PredictionScore,
+    pub line_score: LineScore,
+    pub draw_offer: Option<DrawOffer>, // the latest offer, until the next move
+    pub swapped: bool, // the pie rule was used this game; first_player has changed hands
+    pub clock: Option<Clock>,
+    // What settles the game, consulted in order; the solver and strategies
+    // still play by config.rules()
+    pub win_conditions: Vec<Arc<dyn WinCondition>>,
+    // Who wins when the win conditions pick both sides; set like them, only
+    // through GameBuilder
+    pub double_win: DoubleWin,
+}
+
+impl Model {
+    pub fn new(config: Config) -> Self {
+        let board = Model::initial_board(&config);
+        Self {
+            rng: seeded(config.seed),
+            board,
+            available: get_available_cells(&board),
+            clock: Model::initial_clock(&config),
+            win_conditions: default_conditions(config.rules()),
+            double_win: DoubleWin::default(),
+            config,
+            status: GameStatus::NotFinished,
+            first_player: None,
+            history: Vec::new(),
+            predictions: PredictionScore::default(),
+            line_score: LineScore::default(),
+            draw_offer: None,
+            swapped: false,
+        }
+    }
+
+    // The cells still free, without scanning the board.
+    pub fn available_cells(&self) -> &[usize] {
+        &self.available
+    }
+
+    // Puts `symbol` on cell `i`, which must be unfilled.
+    pub fn fill(&mut self, i: usize, symbol: Cell) {
+        self.board[i] = symbol;
+        self.available.retain(|&j| j != i);
+    }
+
+    // Starts over from `board`.
+    pub fn reset_board(&mut self, board: Board) {
+        self.board = board;
+        self.available = get_available_cells(&board);
+    }
+
+    pub fn initial_clock(config: &Config) -> Option<Clock> {
+        config
+            .clock_secs
+            .map(|secs| Clock::new(Duration::from_secs(secs)))
+    }
+
+    pub fn initial_board(config: &Config) -> Board {
+        let mut board = match config.position {
+            Some((board, _)) => board,
+            None => [Cell::Unfilled; 9],
+        };
+        for &i in &config.blocked {
+            if board[i] == Cell::Unfilled {
+                board[i] = Cell::Blocked;
+            }
+        }
+        board
+    }
+
+    // Side to move in the starting position.
+    pub fn start_symbol(&self) -> Cell {
+        match self.config.position {
+            Some((_, to_move)) => to_move,
+            None => Cell::Nought,
+        }
+    }
+
+    // The player going first plays the side to move in the starting position.
+    pub fn user_symbol(&self) -> Cell {
+        if let Some(Player::User) = self.first_player {
+            self.start_symbol()
+        } else {
+            self.start_symbol().opponent()
+        }
+    }
+
+    // Symbol placed by the k-th move (0-based) of this game.
+    pub fn symbol_of_move(&self, k: usize) -> Cell {
+        if k.is_multiple_of(2) {
+            self.start_symbol()
+        } else {
+            self.start_symbol().opponent()
+        }
+    }
+
+    // The board after the first `n` moves of the history.
+    pub fn board_after_moves(&self, n: usize) -> Board {
+        let mut board = Model::initial_board(&self.config);
+        for (k, &i) in self.history[..n].iter().enumerate() {
+            board[i] = self.symbol_of_move(k);
+        }
+        board
+    }
+
+    pub fn computer_symbol(&self) -> Cell {
+        self.user_symbol().opponent()
+    }
+
+    // Who made the k-th move (0-based) of this game.
+    pub fn player_of_move(&self, k: usize) -> Option<Player> {
+        let first = self.first_player?;
+        Some(if self.config.solo {
+            Player::User
+        } else if k.is_multiple_of(2) {
+            first
+        } else {
+            first.other()
+        })
+    }
+
+    // Under the pie rule the second player may, once, take over the opening
+    // move instead of replying to it.
+    pub fn swap_available(&self) -> bool {
+        self.config.pie
+            && !self.swapped
+            && self.history.len() == 1
+            && self.status == GameStatus::NotFinished
+    }
+
+    // Index in `history` of the user's latest move, so `history[start..]` is
+    // what happened since the user last played.
+    pub fn recent_moves_start(&self) -> usize {
+        (0..self.history.len())
+            .rev()
+            .find(|&k| self.player_of_move(k) == Some(Player::User))
+            .unwrap_or(0)
+    }
+}
+
+// Message
+pub enum Message {
+    CellClicked(usize),
+    PlayerSelected { user_play_first: bool },
+    NewGame,       // restart with the same settings and first player
+    DrawOffer,     // the user offers a draw
+    Swap,          // the user takes over the opening move under the pie rule
+    ComputerStart, // the user lets the computer make its deferred opening move
+    TimeSpent { player: Player, spent: Duration }, // thinking time, for the clock
+    Nothing,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::positions::reachable_positions;
+
+    #[test]
+    fn every_reachable_board_survives_packing() {
+        for board in reachable_positions() {
+            let bits = board.pack();
+            assert!(bits < 1 << 18);
+            assert_eq!(Board::unpack(bits), Some(board));
+        }
+    }
+
+    #[test]
+    fn unpack_refuses_bits_past_the_ninth_cell() {
+        assert_eq!(Board::unpack(1 << 18), None);
+        assert_eq!(Board::unpack(u32::MAX), None);
+        assert_eq!(Board::unpack(0), Some([Cell::Unfilled; 9]));
+    }
+
+    #[test]
+    fn boards_only_come_from_nine_cells() {
+        let cells = [Cell::Nought, Cell::Cross, Cell::Unfilled].repeat(3);
+        let board = Board::from_cells(&cells).unwrap();
+        assert_eq!(board[..], cells[..]);
+        assert_eq!(Board::from_cells(&cells[..8]), Err(WrongLength(8)));
+        assert_eq!(
+            Board::from_cells(&[Cell::Unfilled; 10]),
+            Err(WrongLength(10))
+        );
+        assert_eq!(WrongLength(0).to_string(), "a board has 9 cells, got 0");
+    }
+
+    #[test]
+    fn prediction_accuracy_counts_every_guess() {
+        let mut game = PredictionScore::default();
+        assert_eq!(game.accuracy(), None);
+        game.record(true);
+        game.record(false);
+        game.record(true);
+        game.record(true);
+        assert_eq!(
+            game,
+            PredictionScore {
+                made: 4,
+                correct: 3
+            }
+        );
+        assert_eq!(game.accuracy(), Some(0.75));
+        // A session adds up its games
+        let mut session = PredictionScore::default();
+        session.add(game);
+        session.add(PredictionScore {
+            made: 4,
+            correct: 0,
+        });
+        assert_eq!(session.accuracy(), Some(0.375));
+    }
+}
@@ -0,0 +1,219 @@
+// Compact position notation: three rows separated by '/', then the side to
+// move, e.g. "ox./.../x.. o". Empty cells are written '.' ('_' is accepted)
+// and blocked cells '#'. Without a side, it follows from the marks.
+use crate::helpers::WIN_LINES;
+use crate::model::{Board, Cell};
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum NotationError {
+    Malformed,
+    UnknownSymbol(char),
+    BadCounts, // noughts must equal crosses or lead by one
+    WrongSideToMove,
+    BothWin,
+    MoveAfterWin,
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationError::Malformed => write!(f, "expected \"rrr/rrr/rrr side\""),
+            NotationError::UnknownSymbol(c) => write!(f, "unknown symbol '{}'", c),
+            NotationError::BadCounts => write!(f, "impossible number of noughts and crosses"),
+            NotationError::WrongSideToMove => write!(f, "side to move does not match the board"),
+            NotationError::BothWin => write!(f, "both sides cannot have a line"),
+            NotationError::MoveAfterWin => write!(f, "play continued after a completed line"),
+        }
+    }
+}
+
+fn symbol_char(cell: Cell) -> char {
+    match cell {
+        Cell::Nought => 'o',
+        Cell::Cross => 'x',
+        Cell::Unfilled => '.',
+        Cell::Blocked => '#',
+    }
+}
+
+fn parse_symbol(c: char) -> Result<Cell, NotationError> {
+    match c {
+        'o' => Ok(Cell::Nought),
+        'x' => Ok(Cell::Cross),
+        '.' | '_' => Ok(Cell::Unfilled),
+        '#' => Ok(Cell::Blocked),
+        _ => Err(NotationError::UnknownSymbol(c)),
+    }
+}
+
+pub fn to_notation(board: &Board, to_move: Cell) -> String {
+    let rows: Vec<String> = board
+        .chunks(3)
+        .map(|row| row.iter().map(|&c| symbol_char(c)).collect())
+        .collect();
+    format!("{} {}", rows.join("/"), symbol_char(to_move))
+}
+
+pub fn parse_notation(s: &str) -> Result<(Board, Cell), NotationError> {
+    let mut parts = s.split_whitespace();
+    let (rows, side) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(rows), side, None) => (rows, side),
+        _ => return Err(NotationError::Malformed),
+    };
+    let mut board = [Cell::Unfilled; 9];
+    let rows: Vec<&str> = rows.split('/').collect();
+    if rows.len() != 3 {
+        return Err(NotationError::Malformed);
+    }
+    for (r, row) in rows.iter().enumerate() {
+        let cells: Vec<char> = row.chars().collect();
+        if cells.len() != 3 {
+            return Err(NotationError::Malformed);
+        }
+        for (c, &ch) in cells.iter().enumerate() {
+            board[r * 3 + c] = parse_symbol(ch)?;
+        }
+    }
+    let to_move = match side {
+        Some("o") => Cell::Nought,
+        Some("x") => Cell::Cross,
+        Some(_) => return Err(NotationError::Malformed),
+        None => side_to_move(&board, Cell::Nought)?,
+    };
+    validate_position(&board, to_move)?;
+    Ok((board, to_move))
+}
+
+// Whose turn it is when `first_symbol` made the first move: they have as
+// many marks as the other side or one more.
+pub fn side_to_move(board: &Board, first_symbol: Cell) -> Result<Cell, NotationError> {
+    let count = |symbol| board.iter().filter(|&&c| c == symbol).count();
+    match count(first_symbol).checked_sub(count(first_symbol.opponent())) {
+        Some(0) => Ok(first_symbol),
+        Some(1) => Ok(first_symbol.opponent()),
+        _ => Err(NotationError::BadCounts),
+    }
+}
+
+// Whether the position can arise from legal play with noughts moving first.
+pub fn validate_position(board: &Board, to_move: Cell) -> Result<(), NotationError> {
+    if to_move != side_to_move(board, Cell::Nought)? {
+        return Err(NotationError::WrongSideToMove);
+    }
+    let line_of = |symbol: Cell| {
+        WIN_LINES
+            .iter()
+            .any(|&(a, b, c)| board[a] == symbol && board[b] == symbol && board[c] == symbol)
+    };
+    let nought_line = line_of(Cell::Nought);
+    let cross_line = line_of(Cell::Cross);
+    if nought_line && cross_line {
+        return Err(NotationError::BothWin);
+    }
+    // The winner must have made the last move
+    if (nought_line && to_move != Cell::Cross) || (cross_line && to_move != Cell::Nought) {
+        return Err(NotationError::MoveAfterWin);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::positions::reachable_positions;
+
+    #[test]
+    fn every_reachable_position_round_trips() {
+        for board in reachable_positions() {
+            let to_move = side_to_move(&board, Cell::Nought).unwrap();
+            let text = to_notation(&board, to_move);
+            assert_eq!(parse_notation(&text), Ok((board, to_move)), "{}", text);
+        }
+    }
+
+    #[test]
+    fn side_defaults_to_the_mark_counts() {
+        let (_, to_move) = parse_notation("o../.x./o..").unwrap();
+        assert_eq!(to_move, Cell::Cross);
+        assert_eq!(
+            parse_notation("o_./.../..."),
+            parse_notation("o../.../... x")
+        );
+    }
+
+    #[test]
+    fn malformed_text_is_refused() {
+        assert_eq!(parse_notation(""), Err(NotationError::Malformed));
+        assert_eq!(parse_notation("o../..."), Err(NotationError::Malformed));
+        assert_eq!(
+            parse_notation("o.../.../..."),
+            Err(NotationError::Malformed)
+        );
+        assert_eq!(
+            parse_notation("o../.../... x o"),
+            Err(NotationError::Malformed)
+        );
+        assert_eq!(
+            parse_notation("o../.../... z"),
+            Err(NotationError::Malformed)
+        );
+        assert_eq!(
+            parse_notation("o?./.../..."),
+            Err(NotationError::UnknownSymbol('?'))
+        );
+    }
+
+    #[test]
+    fn illegal_positions_are_refused() {
+        assert_eq!(
+            parse_notation("ooo/.../... x"),
+            Err(NotationError::BadCounts)
+        );
+        assert_eq!(
+            parse_notation("x../.../... o"),
+            Err(NotationError::BadCounts)
+        );
+        assert_eq!(
+            parse_notation("o../.../... o"),
+            Err(NotationError::WrongSideToMove)
+        );
+        assert_eq!(parse_notation("ooo/xxx/... o"), Err(NotationError::BothWin));
+        assert_eq!(
+            parse_notation("ooo/xx./x.. o"),
+            Err(NotationError::MoveAfterWin)
+        );
+    }
+
+    #[test]
+    fn side_to_move_follows_the_mark_counts() {
+        let board = |text| parse_notation(text).unwrap().0;
+        assert_eq!(
+            side_to_move(&[Cell::Unfilled; 9], Cell::Nought),
+            Ok(Cell::Nought)
+        );
+        assert_eq!(
+            side_to_move(&board(".../.o./..."), Cell::Nought),
+            Ok(Cell::Cross)
+        );
+        assert_eq!(
+            side_to_move(&board("x../.o./..."), Cell::Nought),
+            Ok(Cell::Nought)
+        );
+        // With x first, a lone o can't have been played yet, while a lone
+        // x means it is o's turn
+        assert_eq!(
+            side_to_move(&board(".../.o./..."), Cell::Cross),
+            Err(NotationError::BadCounts)
+        );
+        let mut crosses = [Cell::Unfilled; 9];
+        crosses[0] = Cell::Cross;
+        assert_eq!(side_to_move(&crosses, Cell::Cross), Ok(Cell::Nought));
+        // Two marks ahead is impossible whoever started
+        crosses[1] = Cell::Cross;
+        assert_eq!(
+            side_to_move(&crosses, Cell::Nought),
+            Err(NotationError::BadCounts)
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use crate::helpers::{get_available_cells, winning_line};
+use crate::model::{Board, Cell};
+use crate::rules::Rules;
+use crate::symmetry::canonical;
+use std::collections::HashSet;
+
+pub fn is_terminal(board: &Board) -> bool {
+    winning_line(board).is_some() || get_available_cells(board).is_empty()
+}
+
+fn collect(board: Board, to_move: Cell, seen: &mut HashSet<Board>) {
+    if !seen.insert(board) || is_terminal(&board) {
+        return;
+    }
+    for i in get_available_cells(&board) {
+        let mut next = board;
+        next[i] = to_move;
+        collect(next, to_move.opponent(), seen);
+    }
+}
+
+// Every position reachable from the empty board with legal play,
+// including the empty board itself.
+pub fn reachable_positions() -> HashSet<Board> {
+    let mut seen = HashSet::new();
+    collect([Cell::Unfilled; 9], Cell::Nought, &mut seen);
+    seen
+}
+
+pub fn count_positions(unique_by_symmetry: bool) -> usize {
+    let positions = reachable_positions();
+    if unique_by_symmetry {
+        positions
+            .iter()
+            .map(canonical)
+            .collect::<HashSet<Board>>()
+            .len()
+    } else {
+        positions.len()
+    }
+}
+
+// Number of positions in the game tree below this one, itself included,
+// counting every move order separately (549946 from the empty board, of
+// which 255168 are finished games).
+pub fn tree_size(board: &Board, to_move: Cell) -> usize {
+    if is_terminal(board) {
+        return 1;
+    }
+    1 + get_available_cells(board)
+        .into_iter()
+        .map(|i| {
+            let mut next = *board;
+            next[i] = to_move;
+            tree_size(&next, to_move.opponent())
+        })
+        .sum::<usize>()
+}
+
+// Chances of each result for one side when both play uniformly random
+// moves from a position; they add up to 1.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct RandomOdds {
+    pub win: f64,
+    pub draw: f64,
+    pub loss: f64,
+}
+
+// Exact, by walking the whole subtree. Nought moves first, so the side to
+// move follows from the number of marks.
+pub fn random_play_odds(board: &Board, my_symbol: Cell, rules: Rules) -> RandomOdds {
+    let count = |symbol| board.iter().filter(|&&c| c == symbol).count();
+    let to_move = if count(Cell::Nought) > count(Cell::Cross) {
+        Cell::Cross
+    } else {
+        Cell::Nought
+    };
+    odds_from(board, to_move, my_symbol, rules)
+}
+
+fn odds_from(board: &Board, to_move: Cell, my_symbol: Cell, rules: Rules) -> RandomOdds {
+    if let Some((a, _, _)) = winning_line(board) {
+        return if rules.winner(board[a]) == my_symbol {
+            RandomOdds {
+                win: 1.0,
+                ..RandomOdds::default()
+            }
+        } else {
+            RandomOdds {
+                loss: 1.0,
+                ..RandomOdds::default()
+            }
+        };
+    }
+    let available = get_available_cells(board);
+    if available.is_empty() {
+        return RandomOdds {
+            draw: 1.0,
+            ..RandomOdds::default()
+        };
+    }
+    let share = 1.0 / available.len() as f64;
+    let mut odds = RandomOdds::default();
+    for i in available {
+        let mut next = *board;
+        next[i] = to_move;
+        let after = odds_from(&next, to_move.opponent(), my_symbol, rules);
+        odds.win += share * after.win;
+        odds.draw += share * after.draw;
+        odds.loss += share * after.loss;
+    }
+    odds
+}
+
+pub fn win_probability(board: &Board, my_symbol: Cell, rules: Rules) -> f64 {
+    random_play_odds(board, my_symbol, rules).win
+}
+
+// A position after `ply` random legal moves from the empty board, capped at
+// a full board. Games that end before then are thrown away and replayed, so
+// the result always has exactly that many marks.
+#[cfg(feature = "random")]
+pub fn random_position(ply: usize, rng: &mut impl rand::Rng) -> Board {
+    use rand::seq::SliceRandom;
+    let ply = ply.min(9);
+    'retry: loop {
+        let mut board = [Cell::Unfilled; 9];
+        let mut to_move = Cell::Nought;
+        for _ in 0..ply {
+            if is_terminal(&board) {
+                continue 'retry;
+            }
+            let i = *get_available_cells(&board).choose(rng).unwrap();
+            board[i] = to_move;
+            to_move = to_move.opponent();
+        }
+        return board;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_notation;
+
+    #[test]
+    fn empty_board_has_the_known_tree_size() {
+        assert_eq!(tree_size(&[Cell::Unfilled; 9], Cell::Nought), 549946);
+        // A finished game is a tree of one
+        let mut board = [Cell::Unfilled; 9];
+        board[0..3].copy_from_slice(&[Cell::Nought; 3]);
+        assert_eq!(tree_size(&board, Cell::Cross), 1);
+    }
+
+    #[test]
+    fn random_play_odds_add_up_to_one() {
+        for position in [".../.../...", ".../.o./...", "x../.o./..."] {
+            let (board, _) = parse_notation(position).unwrap();
+            for &rules in &[Rules::Standard, Rules::Misere] {
+                for &me in &[Cell::Nought, Cell::Cross] {
+                    let odds = random_play_odds(&board, me, rules);
+                    let total = odds.win + odds.draw + odds.loss;
+                    assert!((total - 1.0).abs() < 1e-9, "{} {:?}", position, odds);
+                    assert_eq!(win_probability(&board, me, rules), odds.win);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn random_positions_have_the_asked_number_of_marks() {
+        use crate::rng::seeded;
+        let mut rng = seeded(Some(11));
+        for ply in 0..=10 {
+            let board = random_position(ply, &mut rng);
+            let count = |symbol| board.iter().filter(|&&c| c == symbol).count();
+            let marks = ply.min(9);
+            assert_eq!(count(Cell::Nought), marks.div_ceil(2), "{}", ply);
+            assert_eq!(count(Cell::Cross), marks / 2, "{}", ply);
+        }
+    }
+}
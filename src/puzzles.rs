@@ -0,0 +1,157 @@
+// Built-in positions with a single idea to find, for `sanmoku puzzles`.
+use crate::model::{Board, Cell};
+use crate::positions::is_terminal;
+use crate::rules::Rules;
+use crate::solver::{move_scores, position_value, Outcome};
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Puzzle {
+    pub title: &'static str,
+    pub position: &'static str, // in the notation of notation.rs
+    pub outcome: Outcome,       // what the side to move can force
+    pub solution: usize,        // one move that forces it (others may too)
+}
+
+pub const PUZZLES: [Puzzle; 7] = [
+    Puzzle {
+        title: "Finish the job",
+        position: "oo./xx./... o",
+        outcome: Outcome::Win,
+        solution: 2,
+    },
+    Puzzle {
+        title: "Block and strike back",
+        position: "o.x/.x./..o o",
+        outcome: Outcome::Win,
+        solution: 6,
+    },
+    Puzzle {
+        title: "Opposite corners",
+        position: "o../.../..x o",
+        outcome: Outcome::Win,
+        solution: 2,
+    },
+    Puzzle {
+        title: "Punish the edge",
+        position: "o../..x/... o",
+        outcome: Outcome::Win,
+        solution: 4,
+    },
+    Puzzle {
+        title: "Answer the center",
+        position: ".../.o./... x",
+        outcome: Outcome::Draw,
+        solution: 0,
+    },
+    Puzzle {
+        title: "Don't take the edge",
+        position: "x../.o./..o x",
+        outcome: Outcome::Draw,
+        solution: 2,
+    },
+    Puzzle {
+        title: "The only move",
+        position: "xo./.o./... x",
+        outcome: Outcome::Draw,
+        solution: 7,
+    },
+];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LineError {
+    Illegal(usize),    // index into the line of a move that can't be played
+    NotOptimal(usize), // index of the first move that gives the result away
+    WrongOutcome(Outcome),
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LineError::Illegal(k) => write!(f, "move {} can't be played", k + 1),
+            LineError::NotOptimal(k) => write!(f, "move {} gives the best result away", k + 1),
+            LineError::WrongOutcome(outcome) => {
+                write!(f, "the side to move can only force a {}", outcome.name())
+            }
+        }
+    }
+}
+
+// Checks an authored solution: every move of `line`, for whichever side is
+// to move, keeps the best result, and that result for `to_move` is
+// `claimed` (when given). Returns the result.
+pub fn verify_line(
+    board: &Board,
+    to_move: Cell,
+    line: &[usize],
+    claimed: Option<Outcome>,
+    rules: Rules,
+) -> Result<Outcome, LineError> {
+    let outcome = Outcome::from_value(position_value(board, to_move, rules));
+    let mut board = *board;
+    let mut side = to_move;
+    for (k, &i) in line.iter().enumerate() {
+        if board[i] != Cell::Unfilled || is_terminal(&board) {
+            return Err(LineError::Illegal(k));
+        }
+        if !is_solution(&board, side, i, rules) {
+            return Err(LineError::NotOptimal(k));
+        }
+        board[i] = side;
+        side = side.opponent();
+    }
+    match claimed {
+        Some(claim) if claim != outcome => Err(LineError::WrongOutcome(outcome)),
+        _ => Ok(outcome),
+    }
+}
+
+// Whether playing `cell` keeps the best result available to `to_move`.
+pub fn is_solution(board: &Board, to_move: Cell, cell: usize, rules: Rules) -> bool {
+    let best = position_value(board, to_move, rules).signum();
+    move_scores(board, to_move, rules)
+        .iter()
+        .any(|&(i, value)| i == cell && value.signum() == best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_notation;
+
+    #[test]
+    fn shipped_puzzles_are_solved_by_their_answers() {
+        for puzzle in &PUZZLES {
+            let (board, to_move) = parse_notation(puzzle.position).unwrap();
+            let value = position_value(&board, to_move, Rules::Standard);
+            assert_eq!(
+                Outcome::from_value(value),
+                puzzle.outcome,
+                "{}",
+                puzzle.title
+            );
+            assert_eq!(board[puzzle.solution], Cell::Unfilled, "{}", puzzle.title);
+            assert!(
+                is_solution(&board, to_move, puzzle.solution, Rules::Standard),
+                "{}",
+                puzzle.title
+            );
+        }
+    }
+
+    #[test]
+    fn verify_line_accepts_best_play_and_flags_the_first_slip() {
+        let (board, to_move) = parse_notation("ox./.o./..x o").unwrap();
+        let verify =
+            |line: &[usize], claimed| verify_line(&board, to_move, line, claimed, Rules::Standard);
+        // The fork, x's block, then the other threat
+        assert_eq!(verify(&[6, 3, 2], Some(Outcome::Win)), Ok(Outcome::Win));
+        assert_eq!(verify(&[2], None), Err(LineError::NotOptimal(0)));
+        assert_eq!(verify(&[6, 3, 7], None), Err(LineError::NotOptimal(2)));
+        assert_eq!(verify(&[6, 6], None), Err(LineError::Illegal(1)));
+        assert_eq!(
+            verify(&[6, 3, 2], Some(Outcome::Draw)),
+            Err(LineError::WrongOutcome(Outcome::Win))
+        );
+    }
+}
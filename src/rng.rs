@@ -0,0 +1,25 @@
+// Source of the computer's random choices. Without the `random` feature
+// (on by default) rand is left out and GameRng is an empty stand-in: every
+// strategy then plays deterministically, see strategy::choose_move.
+
+#[cfg(feature = "random")]
+pub use rand::rngs::StdRng as GameRng;
+
+#[cfg(not(feature = "random"))]
+#[derive(Debug, Clone)]
+pub struct GameRng;
+
+// Reproducible with a seed, different every run without one.
+#[cfg(feature = "random")]
+pub fn seeded(seed: Option<u64>) -> GameRng {
+    use rand::SeedableRng;
+    match seed {
+        Some(seed) => GameRng::seed_from_u64(seed),
+        None => GameRng::from_entropy(),
+    }
+}
+
+#[cfg(not(feature = "random"))]
+pub fn seeded(_seed: Option<u64>) -> GameRng {
+    GameRng
+}
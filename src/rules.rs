@@ -0,0 +1,114 @@
+// Pluggable ways of winning. A condition looks at the board and names the
+// symbol that has won; the game consults a list of them in order.
+use crate::helpers::winning_line;
+use crate::model::{Board, Cell};
+use std::fmt;
+use std::sync::Arc;
+
+pub trait WinCondition: fmt::Debug + Send + Sync {
+    fn check(&self, board: &Board) -> Option<Cell>;
+}
+
+// The standard rule: a completed row, column or diagonal wins. Blocked
+// cells belong to nobody, so lines through them never count.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct ThreeInARow;
+
+impl WinCondition for ThreeInARow {
+    fn check(&self, board: &Board) -> Option<Cell> {
+        winning_line(board).map(|(a, _, _)| board[a])
+    }
+}
+
+// Turns another condition around: whoever meets it loses.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Misere<C>(pub C);
+
+impl<C: WinCondition> WinCondition for Misere<C> {
+    fn check(&self, board: &Board) -> Option<Cell> {
+        self.0.check(board).map(Cell::opponent)
+    }
+}
+
+// The game the solver and the strategies play. Misère only turns around
+// what a completed line means, so one search serves both.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Rules {
+    #[default]
+    Standard,
+    Misere,
+}
+
+impl Rules {
+    // Who has won once `maker` has completed a line.
+    pub fn winner(self, maker: Cell) -> Cell {
+        match self {
+            Rules::Standard => maker,
+            Rules::Misere => maker.opponent(),
+        }
+    }
+}
+
+// The conditions a game starts with: one line, winning or losing.
+pub fn default_conditions(rules: Rules) -> Vec<Arc<dyn WinCondition>> {
+    match rules {
+        Rules::Standard => vec![Arc::new(ThreeInARow)],
+        Rules::Misere => vec![Arc::new(Misere(ThreeInARow))],
+    }
+}
+
+// Winner under the first condition that reports one.
+pub fn winner(board: &Board, conditions: &[&dyn WinCondition]) -> Option<Cell> {
+    conditions
+        .iter()
+        .find_map(|condition| condition.check(board))
+}
+
+// Every symbol the conditions make a winner, judging each side's marks on
+// their own so one side's line can't hide the other's.
+pub fn winners(board: &Board, conditions: &[&dyn WinCondition]) -> Vec<Cell> {
+    let mut found = Vec::new();
+    for side in [Cell::Nought, Cell::Cross] {
+        let mut alone = *board;
+        for cell in alone.iter_mut() {
+            if *cell == side.opponent() {
+                *cell = Cell::Blocked;
+            }
+        }
+        if let Some(symbol) = winner(&alone, conditions) {
+            if !found.contains(&symbol) {
+                found.push(symbol);
+            }
+        }
+    }
+    found
+}
+
+// Who wins a board that makes both sides winners. One line at a time
+// never does, so only registered win conditions can reach it.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DoubleWin {
+    #[default]
+    FirstCompleted,
+    LastCompleted,
+    Draw,
+}
+
+impl DoubleWin {
+    // `boards` are the positions of the game in order, ending with the one
+    // both sides won on. None for a draw.
+    pub fn resolve(self, boards: &[Board], conditions: &[&dyn WinCondition]) -> Option<Cell> {
+        let last = boards.last()?;
+        // Without a history to tell, the order of the conditions decides
+        let first = boards
+            .iter()
+            .find_map(|board| winner(board, conditions))
+            .or_else(|| winner(last, conditions))?;
+        match self {
+            DoubleWin::FirstCompleted => Some(first),
+            DoubleWin::LastCompleted => Some(first.opponent()),
+            DoubleWin::Draw => None,
+        }
+    }
+}
@@ -0,0 +1,164 @@
+// Computer-vs-computer games for statistics.
+use crate::helpers::winning_line;
+use crate::model::{Board, Cell};
+use crate::positions::is_terminal;
+use crate::rng::{seeded, GameRng};
+use crate::rules::Rules;
+use crate::session::Results;
+use crate::strategy::{choose_move, Strategy};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Default)]
+pub struct SelfPlayStats {
+    pub lengths: BTreeMap<usize, usize>, // moves in a game -> number of games
+    pub nought_wins: usize,
+    pub cross_wins: usize,
+    pub draws: usize,
+}
+
+impl SelfPlayStats {
+    pub fn games(&self) -> usize {
+        self.nought_wins + self.cross_wins + self.draws
+    }
+}
+
+// Plays one game between two strategies from the empty board and returns
+// the final board and the number of moves made.
+pub fn play_game(
+    nought: Strategy,
+    cross: Strategy,
+    rules: Rules,
+    rng: &mut GameRng,
+) -> (Board, usize) {
+    let mut board = [Cell::Unfilled; 9];
+    let moves = play_out(&mut board, Cell::Nought, nought, cross, rules, rng);
+    (board, moves.len())
+}
+
+// Who won the finished game on `board`, None for a draw.
+pub fn game_winner(board: &Board, rules: Rules) -> Option<Cell> {
+    winning_line(board).map(|(a, _, _)| rules.winner(board[a]))
+}
+
+// Finishes the game on `board` with `to_move` to play, returning the cells
+// played in order.
+pub fn play_out(
+    board: &mut Board,
+    to_move: Cell,
+    nought: Strategy,
+    cross: Strategy,
+    rules: Rules,
+    rng: &mut GameRng,
+) -> Vec<usize> {
+    let mut to_move = to_move;
+    let mut moves = Vec::new();
+    while !is_terminal(board) {
+        let strategy = if to_move == Cell::Nought {
+            nought
+        } else {
+            cross
+        };
+        match choose_move(strategy, board, to_move, rules, rng) {
+            Some(i) => {
+                board[i] = to_move;
+                moves.push(i);
+            }
+            None => break,
+        }
+        to_move = to_move.opponent();
+    }
+    moves
+}
+
+// `n` optimal-vs-optimal games with random tie-breaking.
+pub fn play_optimal_games(n: usize, seed: u64, rules: Rules) -> SelfPlayStats {
+    let optimal = Strategy::Optimal {
+        randomize_ties: true,
+    };
+    let mut rng = seeded(Some(seed));
+    let mut stats = SelfPlayStats::default();
+    for _ in 0..n {
+        let (board, moves) = play_game(optimal, optimal, rules, &mut rng);
+        *stats.lengths.entry(moves).or_insert(0) += 1;
+        match game_winner(&board, rules) {
+            Some(Cell::Nought) => stats.nought_wins += 1,
+            Some(_) => stats.cross_wins += 1,
+            None => stats.draws += 1,
+        }
+    }
+    stats
+}
+
+// `games` games between `first` and `second`, who take turns to start, all
+// drawing on one generator seeded with `seed`. The results are `first`'s.
+pub fn match_strategies(
+    first: Strategy,
+    second: Strategy,
+    games: usize,
+    seed: u64,
+    rules: Rules,
+) -> Results {
+    let mut rng = seeded(Some(seed));
+    let mut results = Results::default();
+    for k in 0..games {
+        let (nought, cross, first_symbol) = if k.is_multiple_of(2) {
+            (first, second, Cell::Nought)
+        } else {
+            (second, first, Cell::Cross)
+        };
+        let (board, _) = play_game(nought, cross, rules, &mut rng);
+        match game_winner(&board, rules) {
+            Some(symbol) if symbol == first_symbol => results.wins += 1,
+            Some(_) => results.losses += 1,
+            None => results.draws += 1,
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPTIMAL: Strategy = Strategy::Optimal {
+        randomize_ties: true,
+    };
+
+    #[test]
+    fn optimal_play_from_empty_is_a_nine_move_draw() {
+        for &rules in &[Rules::Standard, Rules::Misere] {
+            for seed in 0..10 {
+                let mut board = [Cell::Unfilled; 9];
+                let mut rng = seeded(Some(seed));
+                let moves = play_out(&mut board, Cell::Nought, OPTIMAL, OPTIMAL, rules, &mut rng);
+                assert_eq!(moves.len(), 9, "{:?}", rules);
+                assert_eq!(game_winner(&board, rules), None, "{:?}", rules);
+            }
+        }
+    }
+
+    #[test]
+    fn optimal_games_all_draw() {
+        for &rules in &[Rules::Standard, Rules::Misere] {
+            let stats = play_optimal_games(50, 3, rules);
+            assert_eq!(stats.games(), 50);
+            assert_eq!(stats.draws, 50, "{:?}", rules);
+            assert_eq!(stats.lengths.get(&9), Some(&50));
+        }
+    }
+
+    #[test]
+    fn optimal_matches_draw_each_other() {
+        let results = match_strategies(OPTIMAL, OPTIMAL, 20, 1, Rules::Standard);
+        assert_eq!((results.wins, results.losses, results.draws), (0, 0, 20));
+    }
+
+    // Without the random feature Random has no chance to draw on
+    #[cfg(feature = "random")]
+    #[test]
+    fn optimal_matches_beat_random() {
+        let results = match_strategies(OPTIMAL, Strategy::Random, 100, 1, Rules::Standard);
+        assert_eq!(results.losses, 0);
+        assert!(results.wins > results.draws);
+    }
+}
@@ -0,0 +1,261 @@
+// Every game played since the program started.
+#[cfg(feature = "session")]
+use crate::autosave::write_atomically;
+use crate::config::Config;
+use crate::model::{GameStatus, Model, Player, PredictionScore};
+use crate::strategy::Strategy;
+#[cfg(feature = "session")]
+use std::io;
+#[cfg(feature = "session")]
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameRecord {
+    pub first_player: Option<Player>,
+    pub moves: Vec<usize>,
+    pub status: GameStatus,
+    pub duration: Duration,
+    pub strategy: Strategy,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Session {
+    pub config: Config,
+    pub games: Vec<GameRecord>,
+    pub predictions: PredictionScore,
+}
+
+impl Session {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            games: Vec::new(),
+            predictions: PredictionScore::default(),
+        }
+    }
+
+    pub fn record(&mut self, model: &Model, duration: Duration) {
+        self.games.push(GameRecord {
+            first_player: model.first_player,
+            moves: model.history.clone(),
+            status: model.status,
+            duration,
+            strategy: model.config.strategy,
+        });
+        self.predictions.add(model.predictions);
+    }
+}
+
+// Wins, losses and draws against one kind of computer player.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Results {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Session {
+    // Results over every game.
+    pub fn results(&self) -> Results {
+        let mut results = Results::default();
+        for (_, r) in self.results_by_strategy() {
+            results.wins += r.wins;
+            results.losses += r.losses;
+            results.draws += r.draws;
+        }
+        results
+    }
+
+    // Results per strategy name, in order of first appearance.
+    pub fn results_by_strategy(&self) -> Vec<(&'static str, Results)> {
+        let mut results: Vec<(&'static str, Results)> = Vec::new();
+        for game in &self.games {
+            let name = game.strategy.name();
+            let i = match results.iter().position(|&(n, _)| n == name) {
+                Some(i) => i,
+                None => {
+                    results.push((name, Results::default()));
+                    results.len() - 1
+                }
+            };
+            let r = &mut results[i].1;
+            match game.status {
+                GameStatus::Settled(Player::User) => r.wins += 1,
+                GameStatus::Settled(Player::Computer) => r.losses += 1,
+                GameStatus::Draw => r.draws += 1,
+                GameStatus::NotFinished => (),
+            }
+        }
+        results
+    }
+}
+
+// The games still allowed by --max-games, counting down as games end.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GameCap(Option<usize>);
+
+impl GameCap {
+    pub fn new(max_games: Option<usize>) -> Self {
+        GameCap(max_games)
+    }
+
+    // Counts a finished game; true when it was the last one allowed.
+    pub fn finish_game(&mut self) -> bool {
+        match &mut self.0 {
+            Some(left) => {
+                *left = left.saturating_sub(1);
+                *left == 0
+            }
+            None => false,
+        }
+    }
+}
+
+// Counts the game just finished and only then asks whether to go on.
+// None when the cap ended the session, so nobody is asked.
+pub fn ask_unless_capped<T>(cap: &mut GameCap, ask: impl FnOnce() -> T) -> Option<T> {
+    if cap.finish_game() {
+        None
+    } else {
+        Some(ask())
+    }
+}
+
+pub const CSV_HEADER: &str = "game,first_player,outcome,moves,duration_secs";
+
+// One CSV row per game, numbered from 1, without the header.
+pub fn csv_rows(session: &Session) -> String {
+    session
+        .games
+        .iter()
+        .enumerate()
+        .map(|(n, game)| {
+            let first = match game.first_player {
+                Some(Player::User) => "user",
+                Some(Player::Computer) => "computer",
+                None => "",
+            };
+            let outcome = match game.status {
+                GameStatus::Settled(Player::User) => "win",
+                GameStatus::Settled(Player::Computer) => "loss",
+                GameStatus::Draw => "draw",
+                GameStatus::NotFinished => "unfinished",
+            };
+            format!(
+                "{},{},{},{},{:.1}\n",
+                n + 1,
+                first,
+                outcome,
+                game.moves.len(),
+                game.duration.as_secs_f64()
+            )
+        })
+        .collect()
+}
+
+// Compact binary form of the whole session, including its settings.
+#[cfg(feature = "session")]
+pub fn serialize_session(session: &Session) -> bincode::Result<Vec<u8>> {
+    bincode::serialize(session)
+}
+
+#[cfg(feature = "session")]
+pub fn deserialize_session(bytes: &[u8]) -> bincode::Result<Session> {
+    bincode::deserialize(bytes)
+}
+
+// Saves the session to `path`, replacing the previous save in one step.
+#[cfg(feature = "session")]
+pub fn save_session(path: &Path, session: &Session) -> io::Result<()> {
+    let bytes =
+        serialize_session(session).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    write_atomically(path, bytes)
+}
+
+// Saves the session to `path` once every `every` games; true when it did.
+#[cfg(feature = "session")]
+pub fn save_every(path: &Path, session: &Session, every: usize) -> io::Result<bool> {
+    let played = session.games.len();
+    if played == 0 || !played.is_multiple_of(every) {
+        return Ok(false);
+    }
+    save_session(path, session).map(|()| true)
+}
+
+// None when there is no save or it can't be read.
+#[cfg(feature = "session")]
+pub fn load_session(path: &Path) -> Option<Session> {
+    deserialize_session(&std::fs::read(path).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cap_ends_the_session_without_asking() {
+        let mut cap = GameCap::new(Some(2));
+        let mut asked = 0;
+        assert_eq!(ask_unless_capped(&mut cap, || asked += 1), Some(()));
+        assert_eq!(ask_unless_capped(&mut cap, || asked += 1), None);
+        assert_eq!(asked, 1);
+    }
+
+    #[test]
+    fn no_cap_always_asks() {
+        let mut cap = GameCap::new(None);
+        for _ in 0..100 {
+            assert_eq!(ask_unless_capped(&mut cap, || true), Some(true));
+        }
+    }
+
+    #[cfg(feature = "session")]
+    #[test]
+    fn autosave_writes_the_file_every_n_games() {
+        let path = std::env::temp_dir().join(format!("sanmoku-session-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut session = Session::new(Config::default());
+        let game = Model::new(Config::default());
+        for _ in 0..2 {
+            session.record(&game, Duration::from_secs(1));
+            assert!(!save_every(&path, &session, 3).unwrap());
+            assert!(!path.exists());
+        }
+        session.record(&game, Duration::from_secs(1));
+        assert!(save_every(&path, &session, 3).unwrap());
+        let saved = load_session(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved.unwrap().games.len(), 3);
+    }
+
+    #[test]
+    fn csv_has_one_row_per_game() {
+        let mut session = Session::new(Config::default());
+        let record = |first_player, moves: &[usize], status, secs| GameRecord {
+            first_player,
+            moves: moves.to_vec(),
+            status,
+            duration: Duration::from_millis(secs),
+            strategy: Strategy::Optimal {
+                randomize_ties: false,
+            },
+        };
+        session.games.push(record(
+            Some(Player::User),
+            &[0, 4, 1, 8, 2],
+            GameStatus::Settled(Player::User),
+            12_340,
+        ));
+        session
+            .games
+            .push(record(None, &[], GameStatus::NotFinished, 500));
+        assert_eq!(
+            csv_rows(&session),
+            "1,user,win,5,12.3\n2,,unfinished,0,0.5\n"
+        );
+        assert_eq!(CSV_HEADER.split(',').count(), 5);
+    }
+}
@@ -0,0 +1,505 @@
+// Exhaustive minimax over the 3x3 game tree. Values are from the point of
+// view of the side to move: positive wins, 0 draws, negative loses. A win
+// in n moves (counting both sides) is WIN_VALUE - n and a loss in n moves
+// -(WIN_VALUE - n), so maximizing prefers quick wins and slow losses.
+// Every search takes the Rules it plays by.
+use crate::helpers::{can_win, forks, get_available_cells, symbol_of_move, threats, winning_line};
+use crate::model::{Board, Cell};
+use crate::rules::Rules;
+use crate::symmetry::move_orbits;
+use crate::tablebase::{to_move as side_to_move, Tablebase};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+type Memo = HashMap<(Board, Cell, Rules), i32>;
+
+// More than the longest game, so every decided value keeps its sign.
+const WIN_VALUE: i32 = 10;
+
+thread_local! {
+    // Values never change, so every search shares one cache
+    static MEMO: RefCell<Memo> = RefCell::new(Memo::new());
+}
+
+fn with_memo<T>(f: impl FnOnce(&mut Memo) -> T) -> T {
+    MEMO.with(|memo| f(&mut memo.borrow_mut()))
+}
+
+static TABLEBASE: OnceLock<Tablebase> = OnceLock::new();
+
+// From now on standard positions the tablebase knows are looked up rather
+// than searched. Only the first tablebase given is kept.
+pub fn use_tablebase(tablebase: Tablebase) {
+    let _ = TABLEBASE.set(tablebase);
+}
+
+// The installed tablebase's value, for the position it covers: the standard
+// game with the side to move that the marks imply.
+fn tablebase_value(board: &Board, to_move: Cell, rules: Rules) -> Option<i32> {
+    let tablebase = TABLEBASE.get()?;
+    if rules != Rules::Standard || side_to_move(board) != to_move {
+        return None;
+    }
+    tablebase.value(board)
+}
+
+// Game-theoretic result for the side to move.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Outcome {
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Win => "win",
+            Outcome::Draw => "draw",
+            Outcome::Loss => "loss",
+        }
+    }
+
+    pub fn from_value(value: i32) -> Outcome {
+        match value {
+            v if v > 0 => Outcome::Win,
+            0 => Outcome::Draw,
+            _ => Outcome::Loss,
+        }
+    }
+}
+
+// A result one move further away is worth one less.
+fn one_move_earlier(value: i32) -> i32 {
+    value - value.signum()
+}
+
+fn negamax(board: &Board, to_move: Cell, rules: Rules, memo: &mut Memo) -> i32 {
+    if let Some((a, _, _)) = winning_line(board) {
+        // The previous move completed a line
+        return if rules.winner(board[a]) == to_move {
+            WIN_VALUE
+        } else {
+            -WIN_VALUE
+        };
+    }
+    if let Some(value) = tablebase_value(board, to_move, rules) {
+        return value;
+    }
+    if let Some(&value) = memo.get(&(*board, to_move, rules)) {
+        return value;
+    }
+    let available = get_available_cells(board);
+    let value = if available.is_empty() {
+        0
+    } else {
+        available
+            .iter()
+            .map(|&i| {
+                let mut next = *board;
+                next[i] = to_move;
+                one_move_earlier(-negamax(&next, to_move.opponent(), rules, memo))
+            })
+            .max()
+            .unwrap()
+    };
+    memo.insert((*board, to_move, rules), value);
+    value
+}
+
+pub fn position_value(board: &Board, to_move: Cell, rules: Rules) -> i32 {
+    with_memo(|memo| negamax(board, to_move, rules, memo))
+}
+
+// Result for the first player on the empty board when both sides play
+// perfectly.
+pub fn first_move_outcome() -> Outcome {
+    Outcome::from_value(position_value(
+        &[Cell::Unfilled; 9],
+        Cell::Nought,
+        Rules::Standard,
+    ))
+}
+
+// One opening move per set of symmetric ones (corner, edge, center, by
+// lowest cell) and its result for the first player against the best reply.
+pub fn opening_equity() -> Vec<(usize, Outcome)> {
+    let empty = [Cell::Unfilled; 9];
+    let scores = move_scores(&empty, Cell::Nought, Rules::Standard);
+    move_orbits(&empty, &get_available_cells(&empty))
+        .into_iter()
+        .filter_map(|orbit| {
+            let (_, value) = scores.iter().find(|&&(i, _)| i == orbit[0])?;
+            Some((orbit[0], Outcome::from_value(*value)))
+        })
+        .collect()
+}
+
+// The value of a result `moves` moves away, as position_value gives it.
+pub fn value_of_result(outcome: Outcome, moves: u8) -> i32 {
+    match outcome {
+        Outcome::Win => WIN_VALUE - i32::from(moves),
+        Outcome::Draw => 0,
+        Outcome::Loss => -(WIN_VALUE - i32::from(moves)),
+    }
+}
+
+// Result with best play and how many moves (both sides') it takes. A drawn
+// game runs until the board is full.
+pub fn distance_to_result(board: &Board, to_move: Cell, rules: Rules) -> (Outcome, u8) {
+    let value = position_value(board, to_move, rules);
+    let moves = if value == 0 {
+        get_available_cells(board).len() as i32
+    } else {
+        WIN_VALUE - value.abs()
+    };
+    (Outcome::from_value(value), moves as u8)
+}
+
+// Value for `to_move` of playing each available cell, in cell order.
+pub fn move_scores(board: &Board, to_move: Cell, rules: Rules) -> Vec<(usize, i32)> {
+    with_memo(|memo| {
+        get_available_cells(board)
+            .into_iter()
+            .map(|i| {
+                let mut next = *board;
+                next[i] = to_move;
+                (
+                    i,
+                    one_move_earlier(-negamax(&next, to_move.opponent(), rules, memo)),
+                )
+            })
+            .collect()
+    })
+}
+
+// The cell `to_move` must play to avoid losing, when every other move loses.
+// Empty when there is a choice or nothing saves the game.
+pub fn forced_defensive_moves(board: &Board, to_move: Cell, rules: Rules) -> Vec<usize> {
+    let holding: Vec<usize> = move_scores(board, to_move, rules)
+        .into_iter()
+        .filter(|&(_, v)| v >= 0)
+        .map(|(i, _)| i)
+        .collect();
+    let available = get_available_cells(board).len();
+    if holding.len() == 1 && available > 1 {
+        holding
+    } else {
+        Vec::new()
+    }
+}
+
+// For each move of `to_move` that doesn't end the game: the move, the
+// opponent's best reply and the move's value for `to_move`, in cell order.
+pub fn replies_after(board: &Board, to_move: Cell, rules: Rules) -> Vec<(usize, usize, i32)> {
+    move_scores(board, to_move, rules)
+        .into_iter()
+        .filter_map(|(i, value)| {
+            let mut next = *board;
+            next[i] = to_move;
+            if winning_line(&next).is_some() {
+                return None;
+            }
+            let reply = best_move(&next, to_move.opponent(), rules)?;
+            Some((i, reply, value))
+        })
+        .collect()
+}
+
+// Optimal move (the fastest win or slowest loss), preferring the lowest
+// index among equally good ones.
+pub fn best_move(board: &Board, to_move: Cell, rules: Rules) -> Option<usize> {
+    let scores = move_scores(board, to_move, rules);
+    let best = scores.iter().map(|&(_, v)| v).max()?;
+    scores.iter().find(|&&(_, v)| v == best).map(|&(i, _)| i)
+}
+
+// Best moves for both sides, alternating, until the game ends.
+pub fn principal_variation(board: &Board, to_move: Cell, rules: Rules) -> Vec<usize> {
+    let mut line = Vec::new();
+    let mut board = *board;
+    let mut to_move = to_move;
+    while winning_line(&board).is_none() {
+        match best_move(&board, to_move, rules) {
+            Some(i) => {
+                board[i] = to_move;
+                line.push(i);
+                to_move = to_move.opponent();
+            }
+            None => break,
+        }
+    }
+    line
+}
+
+// How far the moves `counted` picks out of a game from the empty board fell
+// short of perfect play: for each of them, the value of the best move minus
+// the value of the one played. Zero when every move was optimal. `counted`
+// takes the move's index, so a solo player can count both sides.
+pub fn game_regret(history: &[usize], counted: impl Fn(usize) -> bool, rules: Rules) -> i32 {
+    let mut board = [Cell::Unfilled; 9];
+    let mut regret = 0;
+    for (k, &i) in history.iter().enumerate() {
+        let symbol = symbol_of_move(k);
+        if counted(k) {
+            let scores = move_scores(&board, symbol, rules);
+            let best = scores.iter().map(|&(_, v)| v).max().unwrap_or(0);
+            let played = scores
+                .iter()
+                .find(|&&(j, _)| j == i)
+                .map_or(best, |&(_, v)| v);
+            regret += best - played;
+        }
+        board[i] = symbol;
+    }
+    regret
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MoveGrade {
+    Optimal,
+    Inaccuracy, // gave away a win but still holds the draw
+    Blunder,    // turned a position that wasn't lost into a loss
+}
+
+// How much `mover` lost by going from `before` to `after` compared with the
+// best move available.
+pub fn grade_move(before: &Board, after: &Board, mover: Cell, rules: Rules) -> MoveGrade {
+    // Only the result counts here, not how quickly it comes
+    let best = position_value(before, mover, rules).signum();
+    let achieved = -position_value(after, mover.opponent(), rules).signum();
+    if achieved >= best {
+        MoveGrade::Optimal
+    } else if achieved < 0 {
+        MoveGrade::Blunder
+    } else {
+        MoveGrade::Inaccuracy
+    }
+}
+
+// Rough strategic type of a position, for a quick label in the analysis.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PositionClass {
+    Opening,       // at most one mark on the board
+    ThreatPresent, // either side has a line waiting to be completed
+    ForkAvailable, // the side to move can make two threats at once
+    ForcedDraw,
+    Decided, // someone has won, or can force a win
+}
+
+impl PositionClass {
+    pub fn label(self) -> &'static str {
+        match self {
+            PositionClass::Opening => "opening",
+            PositionClass::ThreatPresent => "threat present",
+            PositionClass::ForkAvailable => "fork available",
+            PositionClass::ForcedDraw => "forced draw",
+            PositionClass::Decided => "decided",
+        }
+    }
+}
+
+// The result for `to_move` once playing on is academic: a win or loss one
+// side can force, or a draw because neither side can complete a line any
+// more. None while best play draws but a mistake could still lose it.
+pub fn is_decided(board: &Board, to_move: Cell, rules: Rules) -> Option<Outcome> {
+    match Outcome::from_value(position_value(board, to_move, rules)) {
+        Outcome::Draw if can_win(board, to_move) || can_win(board, to_move.opponent()) => None,
+        outcome => Some(outcome),
+    }
+}
+
+// The first class that fits, checked in the order they are declared;
+// finished games are decided or drawn.
+pub fn classify(board: &Board, to_move: Cell, rules: Rules) -> PositionClass {
+    if winning_line(board).is_some() {
+        return PositionClass::Decided;
+    }
+    if get_available_cells(board).is_empty() {
+        return PositionClass::ForcedDraw;
+    }
+    let marks = board
+        .iter()
+        .filter(|&&c| matches!(c, Cell::Nought | Cell::Cross))
+        .count();
+    if marks <= 1 {
+        PositionClass::Opening
+    } else if !threats(board, to_move).is_empty() || !threats(board, to_move.opponent()).is_empty()
+    {
+        PositionClass::ThreatPresent
+    } else if !forks(board, to_move).is_empty() {
+        PositionClass::ForkAvailable
+    } else if position_value(board, to_move, rules) == 0 {
+        PositionClass::ForcedDraw
+    } else {
+        PositionClass::Decided
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_notation;
+
+    fn class_of(position: &str) -> PositionClass {
+        let (board, to_move) = parse_notation(position).unwrap();
+        classify(&board, to_move, Rules::Standard)
+    }
+
+    #[test]
+    fn positions_fall_into_each_class() {
+        assert_eq!(class_of(".../.../... o"), PositionClass::Opening);
+        assert_eq!(class_of(".../.o./... x"), PositionClass::Opening);
+        assert_eq!(class_of(".../..o/.xo x"), PositionClass::ThreatPresent);
+        assert_eq!(class_of(".../..o/oxx o"), PositionClass::ForkAvailable);
+        assert_eq!(class_of(".../.../xo. o"), PositionClass::ForcedDraw);
+        assert_eq!(class_of(".../.../.xo o"), PositionClass::Decided);
+    }
+
+    #[test]
+    fn finished_games_are_decided_or_drawn() {
+        assert_eq!(class_of("ooo/xx./... x"), PositionClass::Decided);
+        assert_eq!(class_of("oxo/oox/xox x"), PositionClass::ForcedDraw);
+    }
+
+    #[test]
+    fn principal_variation_from_the_empty_board_is_a_draw() {
+        for &rules in &[Rules::Standard, Rules::Misere] {
+            let empty = [Cell::Unfilled; 9];
+            let line = principal_variation(&empty, Cell::Nought, rules);
+            assert_eq!(line.len(), 9, "{:?}", rules);
+            let mut board = empty;
+            for (k, &i) in line.iter().enumerate() {
+                assert_eq!(board[i], Cell::Unfilled);
+                board[i] = symbol_of_move(k);
+            }
+            assert_eq!(winning_line(&board), None);
+        }
+    }
+
+    #[test]
+    fn perfect_game_has_no_regret() {
+        let line = principal_variation(&[Cell::Unfilled; 9], Cell::Nought, Rules::Standard);
+        assert_eq!(game_regret(&line, |_| true, Rules::Standard), 0);
+    }
+
+    #[test]
+    fn blunder_adds_regret_to_its_side_only() {
+        // Cross answers the center with an edge and loses
+        let mut board = [Cell::Unfilled; 9];
+        board[4] = Cell::Nought;
+        board[1] = Cell::Cross;
+        let mut game = vec![4, 1];
+        game.extend(principal_variation(&board, Cell::Nought, Rules::Standard));
+        let crosses = |k: usize| k % 2 == 1;
+        assert!(game_regret(&game, crosses, Rules::Standard) > 0);
+        assert_eq!(game_regret(&game, |k| !crosses(k), Rules::Standard), 0);
+        assert_eq!(
+            game_regret(&game, |_| true, Rules::Standard),
+            game_regret(&game, crosses, Rules::Standard)
+        );
+    }
+
+    #[test]
+    fn principal_variation_ends_at_the_win() {
+        let mut board = [Cell::Unfilled; 9];
+        board[0] = Cell::Nought;
+        board[1] = Cell::Nought;
+        board[3] = Cell::Cross;
+        board[4] = Cell::Cross;
+        assert_eq!(
+            principal_variation(&board, Cell::Nought, Rules::Standard),
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn forced_wins_count_the_moves_to_the_line() {
+        let distance = |position| {
+            let (board, to_move) = parse_notation(position).unwrap();
+            distance_to_result(&board, to_move, Rules::Standard)
+        };
+        assert_eq!(distance("oo./xx./... o"), (Outcome::Win, 1));
+        // The fork on 6, x's block, then the other threat
+        assert_eq!(distance("ox./.o./..x o"), (Outcome::Win, 3));
+        // x can block only one of o's two threats
+        assert_eq!(distance("o.o/.x./o.x x"), (Outcome::Loss, 2));
+        assert_eq!(distance(".../.../... o"), (Outcome::Draw, 9));
+    }
+
+    #[test]
+    fn perfect_play_from_the_empty_board_draws() {
+        assert_eq!(first_move_outcome(), Outcome::Draw);
+    }
+
+    #[test]
+    fn forced_defence_is_the_one_move_that_holds() {
+        let forced = |position| {
+            let (board, to_move) = parse_notation(position).unwrap();
+            forced_defensive_moves(&board, to_move, Rules::Standard)
+        };
+        // Only blocking the top row keeps the draw
+        assert_eq!(forced("oo./.x./... x"), vec![2]);
+        assert!(forced(".../.../... o").is_empty());
+        // Two threats can't both be blocked
+        assert!(forced("o.o/.x./o.x x").is_empty());
+    }
+
+    #[test]
+    fn replies_pair_each_move_with_the_best_answer() {
+        let (board, to_move) = parse_notation("oo./xx./... o").unwrap();
+        let lost = value_of_result(Outcome::Loss, 2);
+        // Winning on 2 ends the game, so it has no reply; blocking on 5
+        // draws, anything else lets x complete the middle row
+        assert_eq!(
+            replies_after(&board, to_move, Rules::Standard),
+            vec![(5, 2, 0), (6, 5, lost), (7, 5, lost), (8, 5, lost)]
+        );
+    }
+
+    #[test]
+    fn only_forced_results_are_decided() {
+        let decided = |position| {
+            let (board, to_move) = parse_notation(position).unwrap();
+            is_decided(&board, to_move, Rules::Standard)
+        };
+        assert_eq!(decided("ox./.o./..x o"), Some(Outcome::Win));
+        assert_eq!(decided("o.o/.x./o.x x"), Some(Outcome::Loss));
+        assert_eq!(decided(".../.../... o"), None);
+        assert_eq!(decided("oo./.x./... x"), None);
+    }
+
+    #[test]
+    fn opening_equity_has_one_entry_per_kind_of_cell() {
+        // Corner, edge and center, each drawing with best play
+        assert_eq!(
+            opening_equity(),
+            vec![(0, Outcome::Draw), (1, Outcome::Draw), (4, Outcome::Draw)]
+        );
+    }
+
+    #[test]
+    fn leaving_a_threat_unblocked_is_a_blunder() {
+        let (before, _) = parse_notation("oo./.x./... x").unwrap();
+        let play = |cell: usize| {
+            let mut after = before;
+            after[cell] = Cell::Cross;
+            grade_move(&before, &after, Cell::Cross, Rules::Standard)
+        };
+        assert_eq!(play(2), MoveGrade::Optimal);
+        assert_eq!(play(8), MoveGrade::Blunder);
+    }
+
+    #[test]
+    fn letting_a_won_game_go_is_an_inaccuracy() {
+        // o can fork on 6 and win, or settle for a draw on 2
+        let (before, _) = parse_notation("ox./.o./..x o").unwrap();
+        let mut after = before;
+        after[2] = Cell::Nought;
+        assert_eq!(
+            grade_move(&before, &after, Cell::Nought, Rules::Standard),
+            MoveGrade::Inaccuracy
+        );
+    }
+}
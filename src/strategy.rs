@@ -0,0 +1,434 @@
+// How the computer picks its move.
+use crate::helpers::{get_available_cells, threats, winning_moves};
+use crate::model::{Board, Cell};
+use crate::positions::random_play_odds;
+use crate::rng::GameRng;
+use crate::rules::Rules;
+use crate::solver::move_scores;
+#[cfg(feature = "random")]
+use rand::prelude::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Strategy {
+    Random,
+    // Random, but favouring the center and corners (see CELL_WEIGHTS).
+    WeightedRandom,
+    // Weighs making threats against blocking them: 0.0 only defends,
+    // 1.0 only attacks.
+    Heuristic { aggressiveness: f32 },
+    // Perfect play. Among equally good moves it takes the lowest cell, or a
+    // random one when `randomize_ties` is set.
+    Optimal { randomize_ties: bool },
+    // Optimal, except that with probability `error_rate` it settles for a
+    // worse move that still doesn't lose, like a human would.
+    Human { error_rate: f32 },
+    // Never does worse than perfect play, but among the moves that keep the
+    // best result picks the one leaving the opponent the most ways to go
+    // wrong.
+    Annoying,
+    // Plays for the best chance of winning if both sides moved at random
+    // from then on, whatever a careful opponent could do about it.
+    Exploit,
+}
+
+impl Strategy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::Random => "random",
+            Strategy::WeightedRandom => "weighted",
+            Strategy::Heuristic { .. } => "heuristic",
+            Strategy::Optimal { .. } => "optimal",
+            Strategy::Human { .. } => "human",
+            Strategy::Annoying => "annoying",
+            Strategy::Exploit => "exploit",
+        }
+    }
+
+    // Strategies that always answer a position with the same move.
+    pub fn is_deterministic(self) -> bool {
+        match self {
+            Strategy::Random | Strategy::WeightedRandom | Strategy::Human { .. } => false,
+            // The random feature breaks their ties at random
+            Strategy::Heuristic { .. } => !cfg!(feature = "random"),
+            Strategy::Optimal { randomize_ties } => !randomize_ties || !cfg!(feature = "random"),
+            Strategy::Annoying | Strategy::Exploit => true,
+        }
+    }
+
+    // Strategies that can't be played without the random feature.
+    pub fn needs_randomness(self) -> bool {
+        matches!(
+            self,
+            Strategy::Random | Strategy::WeightedRandom | Strategy::Human { .. }
+        )
+    }
+}
+
+// Relative chance of each cell being picked by the weighted random strategy.
+pub const CELL_WEIGHTS: [u32; 9] = [3, 1, 3, 1, 4, 1, 3, 1, 3];
+
+const WIN_SCORE: f32 = 10.0;
+
+// Score of playing `cell` for `symbol` under the heuristic strategy. Under
+// misère the same things count against a move: a line of one's own is to
+// be avoided, and a blocked threat frees the opponent from it.
+pub fn heuristic_score(
+    board: &Board,
+    symbol: Cell,
+    cell: usize,
+    aggressiveness: f32,
+    rules: Rules,
+) -> f32 {
+    let mut next = *board;
+    next[cell] = symbol;
+    let offense = if winning_moves(board, symbol).contains(&cell) {
+        WIN_SCORE
+    } else {
+        threats(&next, symbol).len() as f32
+    };
+    let opponent = symbol.opponent();
+    let blocked = threats(board, opponent).len() - threats(&next, opponent).len();
+    let defense = WIN_SCORE * blocked as f32;
+    let score = aggressiveness * offense + (1.0 - aggressiveness) * defense;
+    match rules {
+        Rules::Standard => score,
+        Rules::Misere => -score,
+    }
+}
+
+// Every move with the best minimax value, in cell order.
+pub fn optimal_moves(board: &Board, symbol: Cell, rules: Rules) -> Vec<usize> {
+    let scores = move_scores(board, symbol, rules);
+    let best = scores.iter().map(|&(_, v)| v).max();
+    scores
+        .iter()
+        .filter(|&&(_, v)| Some(v) == best)
+        .map(|&(i, _)| i)
+        .collect()
+}
+
+// How to choose among equally good moves.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum TieBreak {
+    #[default]
+    LowestIndex,
+    // Seeded, so the same seed picks the same move. Without the random
+    // feature there is nothing to draw from and it takes the lowest index.
+    Random {
+        seed: u64,
+    },
+    MostAggressive, // the move leaving the most threats, then the lowest index
+}
+
+// An optimal move for `symbol`, None if the board is full.
+pub fn best_move_with(
+    board: &Board,
+    symbol: Cell,
+    rules: Rules,
+    policy: TieBreak,
+) -> Option<usize> {
+    let best = optimal_moves(board, symbol, rules);
+    match policy {
+        TieBreak::LowestIndex => best.first().copied(),
+        #[cfg(feature = "random")]
+        TieBreak::Random { seed } => best.choose(&mut StdRng::seed_from_u64(seed)).copied(),
+        // See TieBreak::Random
+        #[cfg(not(feature = "random"))]
+        TieBreak::Random { .. } => best.first().copied(),
+        TieBreak::MostAggressive => best.into_iter().rev().max_by_key(|&i| {
+            let mut next = *board;
+            next[i] = symbol;
+            threats(&next, symbol).len()
+        }),
+    }
+}
+
+// Replies to `cell` that lose for the opponent of `symbol`, which would
+// otherwise have held a better result.
+pub fn traps(board: &Board, symbol: Cell, cell: usize, rules: Rules) -> usize {
+    let mut next = *board;
+    next[cell] = symbol;
+    let scores = move_scores(&next, symbol.opponent(), rules);
+    let best = scores.iter().map(|&(_, v)| v.signum()).max();
+    scores
+        .iter()
+        .filter(|&&(_, v)| Some(v.signum()) != best)
+        .count()
+}
+
+// The move keeping the best result that sets the most traps, the lowest
+// cell among equals.
+pub fn annoying_move(board: &Board, symbol: Cell, rules: Rules) -> Option<usize> {
+    let scores = move_scores(board, symbol, rules);
+    let best = scores.iter().map(|&(_, v)| v.signum()).max()?;
+    scores
+        .iter()
+        .filter(|&&(_, v)| v.signum() == best)
+        .map(|&(i, _)| i)
+        .rev()
+        .max_by_key(|&i| traps(board, symbol, i, rules))
+}
+
+// The move with the highest exact win_probability for `symbol` after it.
+// Equal chances go to the move least likely to lose, then the lowest cell.
+// None if the board is full.
+pub fn best_move_vs_random(board: &Board, symbol: Cell, rules: Rules) -> Option<usize> {
+    let odds = |i: usize| {
+        let mut next = *board;
+        next[i] = symbol;
+        random_play_odds(&next, symbol, rules)
+    };
+    get_available_cells(board)
+        .into_iter()
+        .rev()
+        .max_by(|&a, &b| {
+            let (a, b) = (odds(a), odds(b));
+            (a.win, -a.loss).partial_cmp(&(b.win, -b.loss)).unwrap()
+        })
+}
+
+// Moves worse than the best one that still hold at least a draw.
+pub fn plausible_mistakes(board: &Board, symbol: Cell, rules: Rules) -> Vec<usize> {
+    let scores = move_scores(board, symbol, rules);
+    let best = scores.iter().map(|&(_, v)| v).max();
+    scores
+        .iter()
+        .filter(|&&(_, v)| Some(v) != best && v >= 0)
+        .map(|&(i, _)| i)
+        .collect()
+}
+
+#[cfg(feature = "random")]
+pub fn choose_move(
+    strategy: Strategy,
+    board: &Board,
+    symbol: Cell,
+    rules: Rules,
+    rng: &mut GameRng,
+) -> Option<usize> {
+    let mut availables = get_available_cells(board);
+    availables.shuffle(rng);
+    match strategy {
+        Strategy::Random => availables.pop(),
+        Strategy::WeightedRandom => availables
+            .choose_weighted(rng, |&i| CELL_WEIGHTS[i])
+            .ok()
+            .copied(),
+        Strategy::Heuristic { aggressiveness } => {
+            // The shuffle breaks ties between equally scored cells
+            availables.into_iter().max_by(|&a, &b| {
+                let sa = heuristic_score(board, symbol, a, aggressiveness, rules);
+                let sb = heuristic_score(board, symbol, b, aggressiveness, rules);
+                sa.partial_cmp(&sb).unwrap()
+            })
+        }
+        Strategy::Optimal { randomize_ties } => {
+            let best = optimal_moves(board, symbol, rules);
+            if randomize_ties {
+                best.choose(rng).copied()
+            } else {
+                best.first().copied()
+            }
+        }
+        Strategy::Human { error_rate } => {
+            let mistakes = plausible_mistakes(board, symbol, rules);
+            if !mistakes.is_empty() && rng.gen::<f32>() < error_rate {
+                mistakes.choose(rng).copied()
+            } else {
+                optimal_moves(board, symbol, rules).choose(rng).copied()
+            }
+        }
+        Strategy::Annoying => annoying_move(board, symbol, rules),
+        Strategy::Exploit => best_move_vs_random(board, symbol, rules),
+    }
+}
+
+// Without randomness the heuristic takes the lowest of its best cells and
+// everything else plays the lowest optimal move.
+#[cfg(not(feature = "random"))]
+pub fn choose_move(
+    strategy: Strategy,
+    board: &Board,
+    symbol: Cell,
+    rules: Rules,
+    _rng: &mut GameRng,
+) -> Option<usize> {
+    match strategy {
+        Strategy::Heuristic { aggressiveness } => get_available_cells(board)
+            .into_iter()
+            .rev()
+            .max_by(|&a, &b| {
+                let sa = heuristic_score(board, symbol, a, aggressiveness, rules);
+                let sb = heuristic_score(board, symbol, b, aggressiveness, rules);
+                sa.partial_cmp(&sb).unwrap()
+            }),
+        Strategy::Annoying => annoying_move(board, symbol, rules),
+        Strategy::Exploit => best_move_vs_random(board, symbol, rules),
+        _ => optimal_moves(board, symbol, rules).first().copied(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_notation;
+
+    #[test]
+    fn tie_breaks_pick_among_the_optimal_moves() {
+        let (board, symbol) = parse_notation(".../.x./..o o").unwrap();
+        let best = optimal_moves(&board, symbol, Rules::Standard);
+        assert_eq!(best, vec![0, 1, 2, 3, 5, 6, 7]);
+        let pick = |policy| best_move_with(&board, symbol, Rules::Standard, policy);
+        assert_eq!(pick(TieBreak::LowestIndex), Some(0));
+        // 2, 5, 6 and 7 each line up with 8 for a threat; 0 is blocked by x
+        assert_eq!(pick(TieBreak::MostAggressive), Some(2));
+        let random = pick(TieBreak::Random { seed: 7 });
+        assert!(random.is_some_and(|i| best.contains(&i)));
+        assert_eq!(random, pick(TieBreak::Random { seed: 7 }));
+    }
+
+    // Every position a game can reach that still has a move to make, with
+    // the side to move.
+    fn open_positions() -> Vec<(Board, Cell)> {
+        use crate::notation::side_to_move;
+        use crate::positions::{is_terminal, reachable_positions};
+        reachable_positions()
+            .into_iter()
+            .filter(|board| !is_terminal(board))
+            .map(|board| (board, side_to_move(&board, Cell::Nought).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn annoying_move_never_gives_up_the_result() {
+        for (board, symbol) in open_positions() {
+            let scores = move_scores(&board, symbol, Rules::Standard);
+            let best = scores.iter().map(|&(_, v)| v.signum()).max();
+            let cell = annoying_move(&board, symbol, Rules::Standard).unwrap();
+            let (_, value) = scores.iter().find(|&&(i, _)| i == cell).unwrap();
+            assert_eq!(Some(value.signum()), best, "{:?}", board);
+        }
+    }
+
+    #[test]
+    fn random_opponents_are_best_met_in_the_center() {
+        use crate::positions::win_probability;
+        use crate::solver::best_move;
+        let empty = [Cell::Unfilled; 9];
+        // Every opening draws, so minimax just takes the lowest cell
+        assert_eq!(best_move(&empty, Cell::Nought, Rules::Standard), Some(0));
+        assert_eq!(
+            best_move_vs_random(&empty, Cell::Nought, Rules::Standard),
+            Some(4)
+        );
+        let chance = |cell: usize| {
+            let mut board = empty;
+            board[cell] = Cell::Nought;
+            win_probability(&board, Cell::Nought, Rules::Standard)
+        };
+        assert!(chance(4) > chance(0));
+    }
+
+    #[test]
+    fn aggressiveness_decides_between_winning_and_blocking() {
+        // x can win on 5 or block o on 2
+        let (board, symbol) = parse_notation("oo./xx./o.. x").unwrap();
+        let score = |cell, aggressiveness, rules| {
+            heuristic_score(&board, symbol, cell, aggressiveness, rules)
+        };
+        assert_eq!(score(5, 1.0, Rules::Standard), WIN_SCORE);
+        assert_eq!(score(2, 0.0, Rules::Standard), WIN_SCORE);
+        assert_eq!(score(5, 0.0, Rules::Standard), 0.0);
+        assert!(score(5, 1.0, Rules::Standard) > score(2, 1.0, Rules::Standard));
+        for &aggressiveness in &[0.0, 1.0] {
+            for cell in get_available_cells(&board) {
+                assert_eq!(
+                    score(cell, aggressiveness, Rules::Misere),
+                    -score(cell, aggressiveness, Rules::Standard)
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn randomized_ties_only_ever_pick_optimal_moves() {
+        use crate::rng::seeded;
+        let strategy = Strategy::Optimal {
+            randomize_ties: true,
+        };
+        let (board, symbol) = parse_notation(".../.x./..o o").unwrap();
+        let best = optimal_moves(&board, symbol, Rules::Standard);
+        let mut picked = Vec::new();
+        for seed in 0..200 {
+            let cell = choose_move(
+                strategy,
+                &board,
+                symbol,
+                Rules::Standard,
+                &mut seeded(Some(seed)),
+            )
+            .unwrap();
+            assert!(best.contains(&cell), "seed {} picked {}", seed, cell);
+            if !picked.contains(&cell) {
+                picked.push(cell);
+            }
+        }
+        // The ties really are broken at random
+        assert_eq!(picked.len(), best.len());
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn weighted_random_favours_the_center_over_an_edge() {
+        use crate::rng::seeded;
+        let empty = [Cell::Unfilled; 9];
+        let mut counts = [0; 9];
+        for seed in 0..2000 {
+            let cell = choose_move(
+                Strategy::WeightedRandom,
+                &empty,
+                Cell::Nought,
+                Rules::Standard,
+                &mut seeded(Some(seed)),
+            )
+            .unwrap();
+            counts[cell] += 1;
+        }
+        // Weights 4 and 1: about 400 against 100
+        assert!(counts[4] > 2 * counts[1], "{:?}", counts);
+        assert!(counts[4] > 2 * counts[7], "{:?}", counts);
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn human_without_errors_plays_only_optimal_moves() {
+        use crate::rng::seeded;
+        let mut rng = seeded(Some(5));
+        let strategy = Strategy::Human { error_rate: 0.0 };
+        for (board, symbol) in open_positions() {
+            let cell = choose_move(strategy, &board, symbol, Rules::Standard, &mut rng).unwrap();
+            assert!(optimal_moves(&board, symbol, Rules::Standard).contains(&cell));
+        }
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn human_always_erring_still_never_loses() {
+        use crate::selfplay::match_strategies;
+        let optimal = Strategy::Optimal {
+            randomize_ties: true,
+        };
+        let human = Strategy::Human { error_rate: 1.0 };
+        let results = match_strategies(human, optimal, 100, 9, Rules::Standard);
+        assert_eq!(results.losses, 0);
+        // The mistakes it can make let a forced win go, but hold the draw
+        let (board, symbol) = parse_notation("ox./.o./..x o").unwrap();
+        let mistakes = plausible_mistakes(&board, symbol, Rules::Standard);
+        assert!(mistakes.contains(&2));
+        for (cell, value) in move_scores(&board, symbol, Rules::Standard) {
+            assert_eq!(mistakes.contains(&cell), value == 0, "{}", cell);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::model::{Board, Cell, PackedBoard};
+
+// The eight symmetries of the square board.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Transform {
+    Identity,
+    Rotate90, // clockwise
+    Rotate180,
+    Rotate270,
+    ReflectHorizontal,   // mirror left and right
+    ReflectVertical,     // mirror top and bottom
+    ReflectDiagonal,     // across the 0-4-8 diagonal
+    ReflectAntiDiagonal, // across the 2-4-6 diagonal
+}
+
+impl Transform {
+    pub const ALL: [Transform; 8] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+        Transform::ReflectHorizontal,
+        Transform::ReflectVertical,
+        Transform::ReflectDiagonal,
+        Transform::ReflectAntiDiagonal,
+    ];
+
+    // Where the cell at index `i` ends up after the transform.
+    pub fn map_index(self, i: usize) -> usize {
+        let (r, c) = (i / 3, i % 3);
+        let (r, c) = match self {
+            Transform::Identity => (r, c),
+            Transform::Rotate90 => (c, 2 - r),
+            Transform::Rotate180 => (2 - r, 2 - c),
+            Transform::Rotate270 => (2 - c, r),
+            Transform::ReflectHorizontal => (r, 2 - c),
+            Transform::ReflectVertical => (2 - r, c),
+            Transform::ReflectDiagonal => (c, r),
+            Transform::ReflectAntiDiagonal => (2 - c, 2 - r),
+        };
+        r * 3 + c
+    }
+
+    pub fn apply(self, board: &Board) -> Board {
+        let mut out = [Cell::Unfilled; 9];
+        for (i, &cell) in board.iter().enumerate() {
+            out[self.map_index(i)] = cell;
+        }
+        out
+    }
+}
+
+pub fn rotate(board: &Board) -> Board {
+    Transform::Rotate90.apply(board)
+}
+
+pub fn reflect_horizontal(board: &Board) -> Board {
+    Transform::ReflectHorizontal.apply(board)
+}
+
+// Distinct number for every board, two bits per cell.
+pub fn board_key(board: &Board) -> u32 {
+    board.pack()
+}
+
+// Representative shared by all boards equivalent under rotation/reflection.
+pub fn canonical(board: &Board) -> Board {
+    Transform::ALL
+        .iter()
+        .map(|t| t.apply(board))
+        .min_by_key(board_key)
+        .unwrap()
+}
+
+// Whether `b` is a rotation or reflection of `a` (including `a` itself).
+pub fn is_equivalent(a: &Board, b: &Board) -> bool {
+    Transform::ALL.iter().any(|t| t.apply(a) == *b)
+}
+
+// `boards` with later boards equivalent to an earlier one left out.
+pub fn dedupe_equivalent(boards: &[Board]) -> Vec<Board> {
+    let mut kept: Vec<Board> = Vec::new();
+    for board in boards {
+        if !kept.iter().any(|k| is_equivalent(k, board)) {
+            kept.push(*board);
+        }
+    }
+    kept
+}
+
+// Transforms that leave the board unchanged (its stabilizer): all 8 for the
+// empty board, just the identity for a position without symmetry.
+pub fn symmetry_group(board: &Board) -> Vec<Transform> {
+    Transform::ALL
+        .iter()
+        .copied()
+        .filter(|t| t.apply(board) == *board)
+        .collect()
+}
+
+// Groups `cells` into sets that are equivalent given the current position,
+// so each group's moves lead to positions that are rotations/reflections of
+// each other. Groups are ordered by their lowest cell.
+pub fn move_orbits(board: &Board, cells: &[usize]) -> Vec<Vec<usize>> {
+    let symmetries = symmetry_group(board);
+    let mut orbits: Vec<Vec<usize>> = Vec::new();
+    for &i in cells {
+        if orbits.iter().any(|orbit| orbit.contains(&i)) {
+            continue;
+        }
+        let mut orbit: Vec<usize> = symmetries
+            .iter()
+            .map(|t| t.map_index(i))
+            .filter(|j| cells.contains(j))
+            .collect();
+        orbit.sort_unstable();
+        orbit.dedup();
+        orbits.push(orbit);
+    }
+    orbits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_board_has_corner_edge_and_center_moves() {
+        let cells: Vec<usize> = (0..9).collect();
+        assert_eq!(
+            move_orbits(&[Cell::Unfilled; 9], &cells),
+            vec![vec![0, 2, 6, 8], vec![1, 3, 5, 7], vec![4]]
+        );
+    }
+
+    #[test]
+    fn marks_break_the_symmetry() {
+        // With o in a corner only the reflection across it is left
+        let mut board = [Cell::Unfilled; 9];
+        board[0] = Cell::Nought;
+        let cells: Vec<usize> = (1..9).collect();
+        assert_eq!(
+            move_orbits(&board, &cells),
+            vec![vec![1, 3], vec![2, 6], vec![4], vec![5, 7], vec![8]]
+        );
+    }
+}
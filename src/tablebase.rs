@@ -0,0 +1,151 @@
+// Precomputed outcome of every reachable position, and how many moves it
+// takes, keyed by the canonical board so symmetric positions share an
+// entry. Once installed with solver::use_tablebase the solver reads the
+// standard game from here instead of searching it.
+use crate::model::{Board, Cell};
+use crate::positions::reachable_positions;
+use crate::rules::Rules;
+use crate::solver::{distance_to_result, value_of_result, Outcome};
+use crate::symmetry::{board_key, canonical};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub struct Tablebase {
+    entries: HashMap<u32, (Outcome, u8)>,
+}
+
+// Noughts move first, so equal counts mean it is noughts' turn.
+pub fn to_move(board: &Board) -> Cell {
+    let noughts = board.iter().filter(|&&c| c == Cell::Nought).count();
+    let crosses = board.iter().filter(|&&c| c == Cell::Cross).count();
+    if noughts == crosses {
+        Cell::Nought
+    } else {
+        Cell::Cross
+    }
+}
+
+fn outcome_char(outcome: Outcome) -> char {
+    match outcome {
+        Outcome::Win => 'W',
+        Outcome::Draw => 'D',
+        Outcome::Loss => 'L',
+    }
+}
+
+pub fn build_tablebase() -> Tablebase {
+    let mut entries = HashMap::new();
+    for board in reachable_positions() {
+        let board = canonical(&board);
+        entries
+            .entry(board_key(&board))
+            .or_insert_with(|| distance_to_result(&board, to_move(&board), Rules::Standard));
+    }
+    Tablebase { entries }
+}
+
+impl Tablebase {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Outcome for the side to move, or None for positions that can't arise
+    // from the empty board (such as ones with blocked cells).
+    pub fn lookup(&self, board: &Board) -> Option<Outcome> {
+        self.result(board).map(|(outcome, _)| outcome)
+    }
+
+    // The outcome and the number of moves (both sides') it takes.
+    pub fn result(&self, board: &Board) -> Option<(Outcome, u8)> {
+        self.entries.get(&board_key(&canonical(board))).copied()
+    }
+
+    // The solver's value of `board` for the side to move.
+    pub fn value(&self, board: &Board) -> Option<i32> {
+        self.result(board)
+            .map(|(outcome, moves)| value_of_result(outcome, moves))
+    }
+
+    // One "key outcome moves" line per entry, sorted by key.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut keys: Vec<&u32> = self.entries.keys().collect();
+        keys.sort_unstable();
+        let text: String = keys
+            .iter()
+            .map(|&key| {
+                let (outcome, moves) = self.entries[key];
+                format!("{} {} {}\n", key, outcome_char(outcome), moves)
+            })
+            .collect();
+        fs::write(path, text)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Tablebase> {
+        let bad_line = || io::Error::new(io::ErrorKind::InvalidData, "malformed tablebase line");
+        let mut entries = HashMap::new();
+        for line in fs::read_to_string(path)?.lines() {
+            let fields: Vec<&str> = line.split(' ').collect();
+            let (key, outcome, moves) = match fields[..] {
+                [key, outcome, moves] => (key, outcome, moves),
+                _ => return Err(bad_line()),
+            };
+            let key = key.parse::<u32>().map_err(|_| bad_line())?;
+            let outcome = match outcome {
+                "W" => Outcome::Win,
+                "D" => Outcome::Draw,
+                "L" => Outcome::Loss,
+                _ => return Err(bad_line()),
+            };
+            let moves = moves.parse::<u8>().map_err(|_| bad_line())?;
+            entries.insert(key, (outcome, moves));
+        }
+        Ok(Tablebase { entries })
+    }
+
+    // Reads the tablebase at `path`, generating and saving it if missing.
+    pub fn load_or_build(path: &Path) -> io::Result<Tablebase> {
+        if path.exists() {
+            return Tablebase::load(path);
+        }
+        let tablebase = build_tablebase();
+        tablebase.save(path)?;
+        Ok(tablebase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::position_value;
+    use std::env;
+
+    #[test]
+    fn lookups_agree_with_minimax() {
+        let tablebase = build_tablebase();
+        for board in reachable_positions() {
+            let value = position_value(&board, to_move(&board), Rules::Standard);
+            assert_eq!(tablebase.value(&board), Some(value), "{:?}", board);
+            assert_eq!(tablebase.lookup(&board), Some(Outcome::from_value(value)));
+        }
+    }
+
+    #[test]
+    fn saved_tablebase_loads_the_same() {
+        let path = env::temp_dir().join(format!("sanmoku-tablebase-{}", std::process::id()));
+        let tablebase = build_tablebase();
+        tablebase.save(&path).unwrap();
+        let loaded = Tablebase::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.len(), tablebase.len());
+        for board in reachable_positions() {
+            assert_eq!(loaded.result(&board), tablebase.result(&board));
+        }
+    }
+}
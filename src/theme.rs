@@ -0,0 +1,206 @@
+// The game's prompts and result messages, so they can be reworded or
+// translated without touching the code. A theme file has `key = value`
+// lines like the config file; keys not given keep the language's wording.
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Locale {
+    English,
+    Japanese,
+}
+
+impl Locale {
+    pub fn from_name(name: &str) -> Option<Locale> {
+        match name {
+            "en" => Some(Locale::English),
+            "ja" => Some(Locale::Japanese),
+            _ => None,
+        }
+    }
+
+    fn messages(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::English => &ENGLISH,
+            Locale::Japanese => &JAPANESE,
+        }
+    }
+}
+
+// Every key, so other languages can leave some out.
+const ENGLISH: [(&str, &str); 10] = [
+    ("move-prompt", "What's your move? {range}: "),
+    ("win", "You win, nice!"),
+    ("loss", "You lose, too bad! Try again!"),
+    ("draw", "Draw!"),
+    ("first-player", "Do you want to play first? [y/n]: "),
+    (
+        "play-again",
+        "Play again? [y/n] (or 'diff <strategy>' to change the difficulty): ",
+    ),
+    ("yes-no-again", "Please input 'y' or 'n' :"),
+    ("move-again", "Please input {range} :"),
+    ("cell-taken", "Cell {cell} is taken — did you mean {other}?"),
+    ("cell-unavailable", "The cell {cell} is not available"),
+];
+
+const JAPANESE: [(&str, &str); 10] = [
+    ("move-prompt", "どこに打ちますか? {range}: "),
+    ("win", "あなたの勝ちです!"),
+    ("loss", "あなたの負けです。もう一度どうぞ!"),
+    ("draw", "引き分けです!"),
+    ("first-player", "先手を取りますか? [y/n]: "),
+    (
+        "play-again",
+        "もう一度遊びますか? [y/n] ('diff <strategy>' で難易度を変更): ",
+    ),
+    ("yes-no-again", "'y' か 'n' を入力してください:"),
+    ("move-again", "{range} を入力してください:"),
+    (
+        "cell-taken",
+        "{cell} は埋まっています。{other} のことですか?",
+    ),
+    ("cell-unavailable", "{cell} には打てません"),
+];
+
+// The text for `key` in `locale`, in English where it has none.
+pub fn message(locale: Locale, key: &str) -> Option<&'static str> {
+    let find = |messages: &[(&str, &'static str)]| {
+        messages
+            .iter()
+            .find(|&&(k, _)| k == key)
+            .map(|&(_, text)| text)
+    };
+    find(locale.messages()).or_else(|| find(&ENGLISH))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Theme {
+    pub move_prompt: String, // "{range}" is replaced by the cell labels, e.g. [0-8]
+    pub win: String,
+    pub loss: String,
+    pub draw: String,
+    pub first_player: String, // asked when --first isn't given
+    pub play_again: String,
+    pub yes_no_again: String,     // after an answer that is neither
+    pub move_again: String,       // after input that isn't a move; "{range}" as above
+    pub cell_taken: String,       // "{cell}" was played, "{other}" is the nearest free cell
+    pub cell_unavailable: String, // "{cell}" is taken with no free cell near it
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::for_locale(Locale::English)
+    }
+}
+
+impl Theme {
+    pub fn for_locale(locale: Locale) -> Theme {
+        let text = |key| message(locale, key).unwrap_or_default().to_string();
+        Theme {
+            move_prompt: text("move-prompt"),
+            win: text("win"),
+            loss: text("loss"),
+            draw: text("draw"),
+            first_player: text("first-player"),
+            play_again: text("play-again"),
+            yes_no_again: text("yes-no-again"),
+            move_again: text("move-again"),
+            cell_taken: text("cell-taken"),
+            cell_unavailable: text("cell-unavailable"),
+        }
+    }
+
+    // False for a key that isn't one of the theme's.
+    fn set(&mut self, key: &str, value: String) -> bool {
+        let field = match key {
+            "move-prompt" => &mut self.move_prompt,
+            "win" => &mut self.win,
+            "loss" => &mut self.loss,
+            "draw" => &mut self.draw,
+            "first-player" => &mut self.first_player,
+            "play-again" => &mut self.play_again,
+            "yes-no-again" => &mut self.yes_no_again,
+            "move-again" => &mut self.move_again,
+            "cell-taken" => &mut self.cell_taken,
+            "cell-unavailable" => &mut self.cell_unavailable,
+            _ => return false,
+        };
+        *field = value;
+        true
+    }
+
+    // `text` reworded on top of `self`.
+    pub fn parse(mut self, text: &str) -> Result<Theme, String> {
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim().trim_matches('"').to_string()),
+                None => return Err(format!("line {}: expected key = value", n + 1)),
+            };
+            if !self.set(key, value) {
+                return Err(format!("line {}: unknown key {}", n + 1, key));
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn load(self, path: &Path) -> Result<Theme, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        self.parse(&text)
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn move_prompt(&self, label_range: &str) -> String {
+        self.move_prompt.replace("{range}", label_range)
+    }
+
+    pub fn move_again(&self, label_range: &str) -> String {
+        self.move_again.replace("{range}", label_range)
+    }
+
+    pub fn cell_taken(&self, cell: usize, other: usize) -> String {
+        self.cell_taken
+            .replace("{cell}", &cell.to_string())
+            .replace("{other}", &other.to_string())
+    }
+
+    pub fn cell_unavailable(&self, cell: usize) -> String {
+        self.cell_unavailable.replace("{cell}", &cell.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_changes_the_win_message() {
+        let english = Theme::for_locale(Locale::English);
+        let japanese = Theme::for_locale(Locale::Japanese);
+        assert_eq!(english.win, "You win, nice!");
+        assert_eq!(japanese.win, "あなたの勝ちです!");
+        assert_eq!(Theme::default(), english);
+    }
+
+    #[test]
+    fn every_key_has_a_translation() {
+        for &(key, _) in &ENGLISH {
+            let japanese = message(Locale::Japanese, key).unwrap();
+            assert_ne!(Some(japanese), message(Locale::English, key), "{}", key);
+        }
+        assert_eq!(message(Locale::Japanese, "no-such-key"), None);
+    }
+
+    #[test]
+    fn re_asks_fill_in_their_cells() {
+        let theme = Theme::default();
+        assert_eq!(theme.move_again("[1-9]"), "Please input [1-9] :");
+        assert_eq!(theme.cell_taken(5, 6), "Cell 5 is taken — did you mean 6?");
+        assert_eq!(theme.cell_unavailable(5), "The cell 5 is not available");
+    }
+}
@@ -0,0 +1,56 @@
+// Where the game reads the time from. Turns (update::timed_turn) and whole
+// games are timed through a TimeSource, so a game can be run against
+// ManualTime, which only moves when told to; model::Clock is the --clock
+// budget the turns are charged to.
+use std::time::{Duration, Instant};
+
+pub trait TimeSource {
+    fn now(&self) -> Instant;
+
+    // Time passed since `earlier`, zero if that is still to come.
+    fn since(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+}
+
+// The system's monotonic clock.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct RealTime;
+
+impl TimeSource for RealTime {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+// Stands still until advanced, for reproducible timings.
+#[derive(Debug, Clone)]
+pub struct ManualTime {
+    start: Instant,
+    passed: std::cell::Cell<Duration>,
+}
+
+impl ManualTime {
+    pub fn new() -> Self {
+        ManualTime {
+            start: Instant::now(),
+            passed: std::cell::Cell::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.passed.set(self.passed.get() + by);
+    }
+}
+
+impl Default for ManualTime {
+    fn default() -> Self {
+        ManualTime::new()
+    }
+}
+
+impl TimeSource for ManualTime {
+    fn now(&self) -> Instant {
+        self.start + self.passed.get()
+    }
+}
@@ -0,0 +1,913 @@
+use crate::config::{Config, LineResolution};
+use crate::helpers::WIN_LINES;
+use crate::invariants::check_invariants;
+use crate::model::{Board, Cell, DrawOffer, GameStatus, LineScore, Message, Model, Player};
+use crate::rules::{winners, WinCondition};
+use crate::solver::{is_decided, position_value, Outcome};
+use crate::strategy::choose_move;
+use crate::time::TimeSource;
+use crate::view::{ask_prediction, reveal_prediction};
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+
+pub fn update(model: Model, message: Message) -> Model {
+    let model = match message {
+        Message::CellClicked(selected_cell) => update_board(model, selected_cell),
+        Message::PlayerSelected {
+            user_play_first: flag,
+        } => update_player_selection(model, flag),
+        Message::NewGame => update_new_game(model),
+        Message::DrawOffer => update_draw_offer(model),
+        Message::Swap => update_swap(model),
+        Message::ComputerStart => update_computer_start(model),
+        Message::TimeSpent { player, spent } => update_clock(model, player, spent),
+        Message::Nothing => model,
+    };
+    check_invariants(&model);
+    model
+}
+
+// What a user move led to, for callers driving the game without the view.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PlayOutcome {
+    pub computer_move: Option<usize>,
+    pub status: GameStatus,
+}
+
+// Plays the user's `cell` and whatever the computer replies, as a click on
+// the board does. An illegal move changes nothing.
+pub fn play(model: Model, cell: usize) -> (Model, PlayOutcome) {
+    let before = model.history.len();
+    let model = update(model, Message::CellClicked(cell));
+    let reply = before + 1;
+    let computer_move = if model.player_of_move(reply) == Some(Player::Computer) {
+        model.history.get(reply).copied()
+    } else {
+        None
+    };
+    let outcome = PlayOutcome {
+        computer_move,
+        status: model.status,
+    };
+    (model, outcome)
+}
+
+// Only answers the question asked before the game starts; a stray message
+// later leaves the sides as they are. Knowing the sides, a starting
+// position that is already decided ends the game before anyone moves.
+pub fn update_player_selection(model: Model, is_player_first: bool) -> Model {
+    if model.first_player.is_some() || !model.history.is_empty() {
+        return model;
+    }
+    if is_player_first {
+        update_game_status(Model {
+            first_player: Some(Player::User),
+            ..model
+        })
+    } else {
+        let new_model = update_game_status(Model {
+            first_player: Some(Player::Computer),
+            ..model
+        });
+        open_for_computer(new_model)
+    }
+}
+
+// The computer's opening move, unless the user asked to start it by hand.
+fn open_for_computer(model: Model) -> Model {
+    if model.config.prompt_opening {
+        model
+    } else {
+        update_board_with_computer_move(model)
+    }
+}
+
+pub fn update_computer_start(model: Model) -> Model {
+    if model.player_of_move(model.history.len()) != Some(Player::Computer) {
+        return model;
+    }
+    update_board_with_computer_move(model)
+}
+
+pub fn update_new_game(model: Model) -> Model {
+    // A swap only lasts for the game it was made in
+    let model = if model.swapped {
+        swap_sides(model)
+    } else {
+        model
+    };
+    let mut new_model = Model {
+        status: GameStatus::NotFinished,
+        history: Vec::new(),
+        line_score: LineScore::default(),
+        draw_offer: None,
+        swapped: false,
+        clock: Model::initial_clock(&model.config),
+        ..model
+    };
+    new_model.reset_board(Model::initial_board(&new_model.config));
+    let new_model = update_game_status(new_model);
+    if let Some(Player::Computer) = new_model.first_player {
+        open_for_computer(new_model)
+    } else {
+        new_model
+    }
+}
+
+pub fn update_draw_offer(model: Model) -> Model {
+    if model.status != GameStatus::NotFinished || model.config.solo {
+        return model;
+    }
+    answer_draw_offer(Model {
+        draw_offer: Some(DrawOffer::Pending(Player::User)),
+        ..model
+    })
+}
+
+// The computer takes a draw unless it can force a win.
+pub fn answer_draw_offer(model: Model) -> Model {
+    if let Some(DrawOffer::Pending(Player::User)) = model.draw_offer {
+        let computer_can_win =
+            position_value(&model.board, model.user_symbol(), model.config.rules()) < 0;
+        if computer_can_win {
+            Model {
+                draw_offer: Some(DrawOffer::Declined),
+                ..model
+            }
+        } else {
+            Model {
+                draw_offer: Some(DrawOffer::Accepted),
+                status: GameStatus::Draw,
+                ..model
+            }
+        }
+    } else {
+        model
+    }
+}
+
+// Runs `player`'s clock down; whoever runs out of time loses.
+pub fn update_clock(mut model: Model, player: Player, spent: Duration) -> Model {
+    if model.status != GameStatus::NotFinished {
+        return model;
+    }
+    if let Some(clock) = &mut model.clock {
+        clock.charge(player, spent);
+        if clock.flagged() == Some(player) {
+            model.status = GameStatus::Settled(player.other());
+        }
+    }
+    model
+}
+
+// One pass of the game loop with the clocks read from `time`: `ask` shows
+// the game and returns the user's message. Waiting for it is charged to the
+// user on their turn, and a user out of time loses before the move counts;
+// acting on it, the computer's reply and think delay included, is charged
+// to the computer.
+pub fn timed_turn(
+    model: Model,
+    time: &dyn TimeSource,
+    ask: impl FnOnce(&Model) -> Message,
+) -> Model {
+    let users_turn = model.first_player.is_some()
+        && model.player_of_move(model.history.len()) == Some(Player::User);
+    let turn_started = time.now();
+    let msg = ask(&model);
+    let mut model = model;
+    if users_turn {
+        model = update(
+            model,
+            Message::TimeSpent {
+                player: Player::User,
+                spent: time.since(turn_started),
+            },
+        );
+        if model.status != GameStatus::NotFinished {
+            return model;
+        }
+    }
+    let computing = time.now();
+    let model = update(model, msg);
+    update(
+        model,
+        Message::TimeSpent {
+            player: Player::Computer,
+            spent: time.since(computing),
+        },
+    )
+}
+
+// The players exchange symbols: whoever swaps now owns the opening move, so
+// its maker becomes the one to reply.
+pub fn swap_sides(model: Model) -> Model {
+    Model {
+        first_player: model.first_player.map(Player::other),
+        swapped: !model.swapped,
+        ..model
+    }
+}
+
+pub fn update_swap(model: Model) -> Model {
+    if !model.swap_available() || model.player_of_move(1) != Some(Player::User) {
+        return model;
+    }
+    update_board_with_computer_move(swap_sides(model))
+}
+
+// The computer takes over the opening when the side that made it is better
+// off, and on an equal footing when it took the center.
+pub fn computer_wants_swap(model: &Model) -> bool {
+    let value = position_value(&model.board, model.symbol_of_move(1), model.config.rules());
+    value < 0 || (value == 0 && model.history[0] == 4)
+}
+
+// Moves are only accepted on an unfilled cell while the game is running.
+pub fn is_legal_move(model: &Model, cell: usize) -> bool {
+    move_error(model, cell).is_none()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MoveError {
+    OutOfRange,
+    Occupied,
+    GameOver,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::OutOfRange => write!(f, "not a cell on the board"),
+            MoveError::Occupied => write!(f, "the cell is already taken"),
+            MoveError::GameOver => write!(f, "the game is already over"),
+        }
+    }
+}
+
+pub fn move_error(model: &Model, cell: usize) -> Option<MoveError> {
+    if model.status != GameStatus::NotFinished {
+        Some(MoveError::GameOver)
+    } else {
+        match model.board.get(cell) {
+            None => Some(MoveError::OutOfRange),
+            Some(Cell::Unfilled) => None,
+            Some(_) => Some(MoveError::Occupied),
+        }
+    }
+}
+
+// Plays `moves` for both sides in turn, whoever's move each one is, without
+// asking the computer for any. Fails with the index of the first illegal
+// move.
+pub fn apply_moves(mut model: Model, moves: &[usize]) -> Result<Model, (usize, MoveError)> {
+    for &i in moves {
+        let k = model.history.len();
+        if let Some(e) = move_error(&model, i) {
+            return Err((k, e));
+        }
+        model.fill(i, model.symbol_of_move(k));
+        model.history.push(i);
+        model = update_game_status(model);
+    }
+    Ok(model)
+}
+
+// A fresh game with default options after `moves`, `first` having made the
+// first of them.
+pub fn validate_sequence(first: Player, moves: &[usize]) -> Result<Model, (usize, MoveError)> {
+    from_transcript(Config::default(), first, moves)
+}
+
+// What a saved game needs besides its config and first player: the cells
+// played by both sides, in order. The history already holds nothing else.
+pub fn minimal_transcript(model: &Model) -> Vec<usize> {
+    model.history.clone()
+}
+
+// The game `transcript` records, played again under `config`.
+pub fn from_transcript(
+    config: Config,
+    first: Player,
+    transcript: &[usize],
+) -> Result<Model, (usize, MoveError)> {
+    let mut model = Model::new(config);
+    model.first_player = Some(first);
+    apply_moves(update_game_status(model), transcript)
+}
+
+pub fn update_board_helper(board: &Board, selected_index: usize, cell: Cell) -> Board {
+    let mut new_board = *board;
+    new_board[selected_index] = cell;
+    new_board
+}
+
+pub fn update_board(model: Model, selected_cell: usize) -> Model {
+    // Otherwise the computer would move in the user's place
+    if !is_legal_move(&model, selected_cell) {
+        return model;
+    }
+    let model = update_board_with_user_move(model, selected_cell);
+    if model.swap_available()
+        && model.player_of_move(1) == Some(Player::Computer)
+        && computer_wants_swap(&model)
+    {
+        // The user is now the one to reply to their own opening
+        return swap_sides(model);
+    }
+    update_board_with_computer_move(model)
+}
+
+pub fn update_board_with_user_move(model: Model, selected_cell: usize) -> Model {
+    if !is_legal_move(&model, selected_cell) {
+        return model;
+    }
+    // Solo games alternate symbols for the one player
+    let user_cell_type = model.symbol_of_move(model.history.len());
+    let mut new_model = Model {
+        draw_offer: None,
+        ..model
+    };
+    new_model.fill(selected_cell, user_cell_type);
+    new_model.history.push(selected_cell);
+    update_game_status(new_model)
+}
+
+pub fn update_board_with_computer_move(mut model: Model) -> Model {
+    if model.status != GameStatus::NotFinished || model.config.solo {
+        return model;
+    }
+    let computer_cell_type = model.computer_symbol();
+    // Asking only makes sense with someone at the keyboard
+    let prediction = if model.config.predict && model.config.interactive {
+        ask_prediction(&model)
+    } else {
+        None
+    };
+    let choice = choose_move(
+        model.config.strategy,
+        &model.board,
+        computer_cell_type,
+        model.config.rules(),
+        &mut model.rng,
+    );
+    if let Some(i) = choice {
+        think(model.config.think_delay_ms);
+        if let Some(guess) = prediction {
+            reveal_prediction(&model, guess, i);
+            model.predictions.record(guess == i);
+        }
+        model.fill(i, computer_cell_type);
+        model.history.push(i);
+        update_game_status(model)
+    } else {
+        update_game_status(model)
+    }
+}
+
+fn think(delay_ms: u64) {
+    if delay_ms > 0 {
+        println!("Computer is thinking...");
+        thread::sleep(Duration::from_millis(delay_ms));
+    }
+}
+
+// Cleared lines can free cells forever, so arcade games stop here at the
+// latest.
+pub const MAX_ARCADE_MOVES: usize = 27;
+
+// Arcade mode: every completed line scores for its owner and its cells are
+// locked or cleared; the game goes on until no cell is left, and most lines
+// wins.
+pub fn update_arcade_status(mut model: Model) -> Model {
+    let user_symbol = model.user_symbol();
+    let completed: Vec<(usize, usize, usize)> = WIN_LINES
+        .iter()
+        .copied()
+        .filter(|&(a, b, c)| {
+            matches!(model.board[a], Cell::Nought | Cell::Cross)
+                && model.board[a] == model.board[b]
+                && model.board[a] == model.board[c]
+        })
+        .collect();
+    // Score every line before locking, so one move can complete two
+    for &(a, _, _) in &completed {
+        if model.board[a] == user_symbol {
+            model.line_score.user += 1;
+        } else {
+            model.line_score.computer += 1;
+        }
+    }
+    let emptied = match model.config.line_resolution {
+        LineResolution::Clear => Cell::Unfilled,
+        _ => Cell::Blocked,
+    };
+    let mut board = model.board;
+    for &(a, b, c) in &completed {
+        for i in [a, b, c] {
+            board[i] = emptied;
+        }
+    }
+    model.reset_board(board);
+    if !model.board.contains(&Cell::Unfilled) || model.history.len() >= MAX_ARCADE_MOVES {
+        let LineScore { user, computer } = model.line_score;
+        model.status = if user > computer {
+            GameStatus::Settled(Player::User)
+        } else if computer > user {
+            GameStatus::Settled(Player::Computer)
+        } else {
+            GameStatus::Draw
+        };
+    }
+    model
+}
+
+pub fn update_game_status(model: Model) -> Model {
+    if model.config.arcade() {
+        return update_arcade_status(model);
+    }
+    let registered = model.win_conditions.clone();
+    let conditions: Vec<&dyn WinCondition> = registered.iter().map(|c| c.as_ref()).collect();
+    let model = update_game_status_with(model, &conditions);
+    match decided_status(&model) {
+        Some(status) if model.status == GameStatus::NotFinished => Model { status, ..model },
+        _ => model,
+    }
+}
+
+// With --end-when-decided, the status a running game ends with as soon as
+// no move can change its result. The solver only knows games between two
+// sides that end at the first line.
+pub fn decided_status(model: &Model) -> Option<GameStatus> {
+    let config = &model.config;
+    if !config.end_when_decided || config.arcade() || config.solo {
+        return None;
+    }
+    let k = model.history.len();
+    let to_move = model.player_of_move(k)?;
+    Some(
+        match is_decided(&model.board, model.symbol_of_move(k), config.rules())? {
+            Outcome::Win => GameStatus::Settled(to_move),
+            Outcome::Loss => GameStatus::Settled(to_move.other()),
+            Outcome::Draw => GameStatus::Draw,
+        },
+    )
+}
+
+// Who the configured DoubleWin policy makes the winner when `conditions`
+// make both sides winners, judging by the game's history. None for a draw.
+pub fn resolve_double_win(model: &Model, conditions: &[&dyn WinCondition]) -> Option<Cell> {
+    let boards: Vec<Board> = (0..=model.history.len())
+        .map(|n| model.board_after_moves(n))
+        .collect();
+    model.double_win.resolve(&boards, conditions)
+}
+
+// Settles the game by `conditions` instead of the model's own; with
+// no winner, a full board is a draw. When both sides have won, the
+// configured DoubleWin policy picks one from the history.
+pub fn update_game_status_with(model: Model, conditions: &[&dyn WinCondition]) -> Model {
+    let symbol = match winners(&model.board, conditions).as_slice() {
+        [] => None,
+        [symbol] => Some(*symbol),
+        _ => match resolve_double_win(&model, conditions) {
+            Some(symbol) => Some(symbol),
+            None => {
+                return Model {
+                    status: GameStatus::Draw,
+                    ..model
+                }
+            }
+        },
+    };
+    if let Some(symbol) = symbol {
+        let player = if symbol == model.user_symbol() {
+            Player::User
+        } else {
+            Player::Computer
+        };
+        return Model {
+            status: GameStatus::Settled(player),
+            ..model
+        };
+    }
+    if !model.board.contains(&Cell::Unfilled) {
+        return Model {
+            status: GameStatus::Draw,
+            ..model
+        };
+    }
+    model
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameBuilder;
+    use crate::invariants::invariant_violation;
+    use crate::rules::{DoubleWin, ThreeInARow};
+    use crate::strategy::Strategy;
+    use crate::time::ManualTime;
+    use std::sync::Arc;
+
+    const BUDGET: Duration = Duration::from_secs(5);
+
+    fn clocked_game() -> Model {
+        let config = Config {
+            clock_secs: Some(BUDGET.as_secs()),
+            ..Config::default()
+        };
+        game_with(config, Player::User)
+    }
+
+    // A turn that takes `spent` on the fake clock before playing `cell`.
+    fn turn_taking(model: Model, time: &ManualTime, spent: Duration, cell: usize) -> Model {
+        timed_turn(model, time, |_| {
+            time.advance(spent);
+            Message::CellClicked(cell)
+        })
+    }
+
+    #[test]
+    fn move_timeout_forfeits_at_exactly_the_budget() {
+        let time = ManualTime::new();
+        let model = turn_taking(clocked_game(), &time, BUDGET, 0);
+        assert_eq!(model.status, GameStatus::Settled(Player::Computer));
+        // The late move never reaches the board
+        assert!(model.history.is_empty());
+    }
+
+    #[test]
+    fn move_just_inside_the_budget_is_played() {
+        let time = ManualTime::new();
+        let almost = BUDGET - Duration::from_nanos(1);
+        let model = turn_taking(clocked_game(), &time, almost, 0);
+        assert_eq!(model.status, GameStatus::NotFinished);
+        assert_eq!(model.history.len(), 2);
+        assert_eq!(model.clock.unwrap().user, Duration::from_nanos(1));
+        // The time left runs out on the next move, however quick
+        let cell = model.available_cells()[0];
+        let model = turn_taking(model, &time, Duration::from_nanos(1), cell);
+        assert_eq!(model.status, GameStatus::Settled(Player::Computer));
+    }
+
+    #[test]
+    fn play_reports_the_computer_reply() {
+        let model = GameBuilder::new()
+            .first_player(Player::User)
+            .build()
+            .unwrap();
+        let (model, outcome) = play(model, 4);
+        assert_eq!(outcome.status, GameStatus::NotFinished);
+        let cell = outcome.computer_move.unwrap();
+        assert_eq!(model.history, vec![4, cell]);
+        assert_eq!(model.board[cell], model.computer_symbol());
+        // An illegal move gets no reply
+        let (_, outcome) = play(model, 4);
+        assert_eq!(outcome.computer_move, None);
+    }
+
+    #[test]
+    fn finished_game_refuses_further_moves() {
+        let model = validate_sequence(Player::User, &[0, 3, 1, 4, 2]).unwrap();
+        assert_eq!(model.status, GameStatus::Settled(Player::User));
+        assert_eq!(move_error(&model, 8), Some(MoveError::GameOver));
+        let board = model.board;
+        let model = update_board(model, 8);
+        assert_eq!(model.board, board);
+        assert_eq!(model.history, vec![0, 3, 1, 4, 2]);
+        assert_eq!(
+            apply_moves(model, &[8]).err(),
+            Some((5, MoveError::GameOver))
+        );
+    }
+
+    // Whoever takes the center wins.
+    #[derive(Debug)]
+    struct CenterFilled;
+
+    impl WinCondition for CenterFilled {
+        fn check(&self, board: &Board) -> Option<Cell> {
+            match board[4] {
+                Cell::Nought | Cell::Cross => Some(board[4]),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn registered_condition_settles_the_game() {
+        let model = GameBuilder::new()
+            .win_conditions(vec![Arc::new(CenterFilled), Arc::new(ThreeInARow)])
+            .first_player(Player::User)
+            .build()
+            .unwrap();
+        let (model, outcome) = play(model, 4);
+        assert_eq!(outcome.status, GameStatus::Settled(Player::User));
+        assert_eq!(outcome.computer_move, None);
+        assert_eq!(model.history, vec![4]);
+    }
+
+    // Nought completes the top row, then cross the middle one; the moves go
+    // straight onto the board, as normal play would have stopped at the
+    // first line.
+    fn both_sides_won(policy: DoubleWin) -> Model {
+        let mut model = GameBuilder::new()
+            .double_win(policy)
+            .first_player(Player::User)
+            .build()
+            .unwrap();
+        for &i in &[0, 3, 1, 4, 2, 5] {
+            model.fill(i, model.symbol_of_move(model.history.len()));
+            model.history.push(i);
+        }
+        update_game_status(model)
+    }
+
+    #[test]
+    fn double_win_follows_the_policy() {
+        let cases = [
+            (DoubleWin::FirstCompleted, GameStatus::Settled(Player::User)),
+            (
+                DoubleWin::LastCompleted,
+                GameStatus::Settled(Player::Computer),
+            ),
+            (DoubleWin::Draw, GameStatus::Draw),
+        ];
+        for &(policy, status) in &cases {
+            let model = both_sides_won(policy);
+            assert_eq!(model.status, status, "{:?}", policy);
+            assert_eq!(invariant_violation(&model), None);
+        }
+    }
+
+    #[test]
+    fn misere_line_loses_for_its_maker() {
+        for &first in &[Player::User, Player::Computer] {
+            let mut model = GameBuilder::new().misere(true).build().unwrap();
+            model.first_player = Some(first);
+            // The first player completes the top row
+            for &i in &[0, 3, 1, 4, 2] {
+                model.fill(i, model.symbol_of_move(model.history.len()));
+                model.history.push(i);
+            }
+            let model = update_game_status(model);
+            assert_eq!(
+                model.status,
+                GameStatus::Settled(first.other()),
+                "{:?}",
+                first
+            );
+        }
+    }
+
+    #[test]
+    fn default_condition_is_three_in_a_row() {
+        let model = GameBuilder::new()
+            .first_player(Player::User)
+            .build()
+            .unwrap();
+        let (_, outcome) = play(model, 4);
+        assert_eq!(outcome.status, GameStatus::NotFinished);
+    }
+
+    // A game under `config` against a computer that plays the lowest of its
+    // optimal moves.
+    fn game_with(config: Config, first: Player) -> Model {
+        GameBuilder::from_config(config)
+            .strategy(Strategy::Optimal {
+                randomize_ties: false,
+            })
+            .first_player(first)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn swap_takes_over_the_opening_and_its_wins() {
+        let config = Config {
+            pie: true,
+            ..Config::default()
+        };
+        let model = game_with(config, Player::Computer);
+        assert_eq!(model.history, vec![0]);
+        assert!(model.swap_available());
+        let model = update(model, Message::Swap);
+        assert!(model.swapped);
+        assert_eq!(model.first_player, Some(Player::User));
+        assert_eq!(model.user_symbol(), Cell::Nought);
+        assert_eq!(model.player_of_move(0), Some(Player::User));
+        // The computer now replies to the opening with its own symbol
+        assert_eq!(model.history, vec![0, 4]);
+        assert_eq!(model.board[4], model.computer_symbol());
+        assert_eq!(model.computer_symbol(), Cell::Cross);
+        // The opening's line wins for the user who took it over
+        let model = apply_moves(model, &[1, 3, 2]).unwrap();
+        assert_eq!(model.status, GameStatus::Settled(Player::User));
+    }
+
+    #[test]
+    fn computer_accepts_a_draw_it_cannot_beat() {
+        let model = game_with(Config::default(), Player::User);
+        let model = update(model, Message::DrawOffer);
+        assert_eq!(model.draw_offer, Some(DrawOffer::Accepted));
+        assert_eq!(model.status, GameStatus::Draw);
+    }
+
+    #[test]
+    fn computer_declines_a_draw_when_it_wins() {
+        // Crosses on 0, 4 and 6 threaten three lines with noughts to move
+        let model = validate_sequence(Player::User, &[1, 0, 5, 4, 7, 6]).unwrap();
+        let model = update(model, Message::DrawOffer);
+        assert_eq!(model.draw_offer, Some(DrawOffer::Declined));
+        assert_eq!(model.status, GameStatus::NotFinished);
+        // The answer lasts until the next move
+        let model = update_board_with_user_move(model, 8);
+        assert_eq!(model.draw_offer, None);
+    }
+
+    // Arcade game whose last move, nought on 0, completes the top row and
+    // the left column at once; nothing else is free.
+    fn double_line_game(resolution: LineResolution) -> Model {
+        let config = Config {
+            line_resolution: resolution,
+            ..Config::default()
+        };
+        let mut model = Model::new(config);
+        model.first_player = Some(Player::User);
+        for &i in &[1, 4, 2, 5, 3, 7, 6, 8, 0] {
+            model.fill(i, model.symbol_of_move(model.history.len()));
+            model.history.push(i);
+        }
+        update_game_status(model)
+    }
+
+    #[test]
+    fn two_lines_at_once_score_two_points() {
+        let model = double_line_game(LineResolution::Lock);
+        assert_eq!(
+            model.line_score,
+            LineScore {
+                user: 2,
+                computer: 0
+            }
+        );
+        assert_eq!(model.status, GameStatus::Settled(Player::User));
+    }
+
+    #[test]
+    fn cleared_lines_free_their_cells_and_locked_lines_block_them() {
+        let line_cells = [0, 1, 2, 3, 6];
+        let cleared = double_line_game(LineResolution::Clear);
+        assert_eq!(cleared.line_score.user, 2);
+        assert_eq!(cleared.status, GameStatus::NotFinished);
+        for &i in &line_cells {
+            assert_eq!(cleared.board[i], Cell::Unfilled);
+        }
+        let locked = double_line_game(LineResolution::Lock);
+        for &i in &line_cells {
+            assert_eq!(locked.board[i], Cell::Blocked);
+        }
+        assert_eq!(locked.board[4], Cell::Cross);
+    }
+
+    #[test]
+    fn solo_game_never_makes_a_computer_move() {
+        let config = Config {
+            solo: true,
+            ..Config::default()
+        };
+        let model = game_with(config, Player::Computer);
+        assert!(model.history.is_empty());
+        let model = update(model, Message::CellClicked(4));
+        let model = update(model, Message::CellClicked(0));
+        assert_eq!(model.history, vec![4, 0]);
+        assert_ne!(model.board[4], model.board[0]);
+        let model = update_board_with_computer_move(model);
+        let model = update_computer_start(model);
+        assert_eq!(model.history, vec![4, 0]);
+    }
+
+    #[test]
+    fn stray_player_selection_keeps_the_sides() {
+        let model = game_with(Config::default(), Player::User);
+        let model = update(model, Message::CellClicked(4));
+        let before = model.clone();
+        let model = update(
+            model,
+            Message::PlayerSelected {
+                user_play_first: false,
+            },
+        );
+        assert_eq!(model.first_player, Some(Player::User));
+        assert_eq!(model.history, before.history);
+        assert_eq!(model.board, before.board);
+    }
+
+    #[test]
+    fn computer_opening_waits_only_when_prompted() {
+        let automatic = update(
+            Model::new(Config::default()),
+            Message::PlayerSelected {
+                user_play_first: false,
+            },
+        );
+        assert_eq!(automatic.history.len(), 1);
+
+        let config = Config {
+            prompt_opening: true,
+            ..Config::default()
+        };
+        let deferred = update(
+            Model::new(config),
+            Message::PlayerSelected {
+                user_play_first: false,
+            },
+        );
+        assert!(deferred.history.is_empty());
+        let deferred = update(deferred, Message::ComputerStart);
+        assert_eq!(deferred.history.len(), 1);
+        assert_eq!(deferred.player_of_move(1), Some(Player::User));
+        let deferred = update(deferred, Message::ComputerStart);
+        assert_eq!(deferred.history.len(), 1);
+    }
+
+    #[test]
+    fn blocked_cells_neither_win_nor_take_moves() {
+        let model = GameBuilder::new()
+            .blocked(&[3, 4, 5])
+            .first_player(Player::User)
+            .build()
+            .unwrap();
+        // The blocked middle row is nobody's line
+        assert_eq!(model.status, GameStatus::NotFinished);
+        assert_eq!(move_error(&model, 4), Some(MoveError::Occupied));
+        let model = apply_moves(model, &[0, 6, 1, 7]).unwrap();
+        assert_eq!(model.status, GameStatus::NotFinished);
+        let model = apply_moves(model, &[2]).unwrap();
+        assert_eq!(model.status, GameStatus::Settled(Player::User));
+    }
+
+    #[test]
+    fn valid_sequence_gives_the_game_it_records() {
+        let moves = [4, 0, 8, 2, 1, 3, 7];
+        let model = validate_sequence(Player::Computer, &moves).unwrap();
+        assert_eq!(model.history, moves);
+        assert_eq!(model.board[4], Cell::Nought);
+        assert_eq!(model.board[0], Cell::Cross);
+        // 7 completes the computer's middle column
+        assert_eq!(model.status, GameStatus::Settled(Player::Computer));
+    }
+
+    #[test]
+    fn invalid_sequences_name_the_first_bad_move() {
+        assert_eq!(
+            validate_sequence(Player::User, &[4, 0, 4]).unwrap_err(),
+            (2, MoveError::Occupied)
+        );
+        assert_eq!(
+            validate_sequence(Player::User, &[4, 9]).unwrap_err(),
+            (1, MoveError::OutOfRange)
+        );
+        assert_eq!(
+            validate_sequence(Player::User, &[0, 3, 1, 4, 2, 5]).unwrap_err(),
+            (5, MoveError::GameOver)
+        );
+    }
+
+    #[test]
+    fn transcript_rebuilds_the_game() {
+        let config = Config {
+            blocked: vec![2],
+            ..Config::default()
+        };
+        let mut model = game_with(config.clone(), Player::User);
+        while model.status == GameStatus::NotFinished {
+            let cell = model.available_cells()[0];
+            model = play(model, cell).0;
+        }
+        let transcript = minimal_transcript(&model);
+        let rebuilt = from_transcript(config, Player::User, &transcript).unwrap();
+        assert_eq!(rebuilt.board, model.board);
+        assert_eq!(rebuilt.status, model.status);
+        assert_eq!(rebuilt.history, model.history);
+    }
+
+    #[test]
+    fn going_to_a_move_matches_playing_up_to_it() {
+        use crate::helpers::board_after;
+        let moves = [4, 0, 8, 2, 1, 7, 6, 3, 5];
+        let full = validate_sequence(Player::User, &moves).unwrap();
+        for n in 0..=moves.len() {
+            let stepped = validate_sequence(Player::User, &moves[..n]).unwrap();
+            let goto = board_after(&[Cell::Unfilled; 9], &moves[..n]);
+            assert_eq!(goto, stepped.board, "after {} moves", n);
+            assert_eq!(
+                full.board_after_moves(n),
+                stepped.board,
+                "after {} moves",
+                n
+            );
+        }
+    }
+}
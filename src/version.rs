@@ -0,0 +1,50 @@
+// Build details for bug reports: `sanmoku --version` or `?version` at the
+// move prompt.
+use crate::config::Config;
+
+const FEATURES: [(&str, bool); 6] = [
+    ("random", cfg!(feature = "random")),
+    ("clipboard", cfg!(feature = "clipboard")),
+    ("session", cfg!(feature = "session")),
+    ("autosave", cfg!(feature = "autosave")),
+    ("dump", cfg!(feature = "dump")),
+    ("png", cfg!(feature = "png")),
+];
+
+pub fn version_info() -> String {
+    let mut text = format!("SanmokuRust {}", env!("CARGO_PKG_VERSION"));
+    if let Some(commit) = option_env!("SANMOKU_GIT_COMMIT") {
+        text += &format!(" ({})", commit);
+    }
+    let features: Vec<&str> = FEATURES
+        .iter()
+        .filter(|&&(_, enabled)| enabled)
+        .map(|&(name, _)| name)
+        .collect();
+    let features = if features.is_empty() {
+        "none".to_string()
+    } else {
+        features.join(", ")
+    };
+    let defaults = Config::default();
+    text += &format!(
+        "\nFeatures: {}\nDefaults: {} strategy, {} layout",
+        features,
+        defaults.strategy.name(),
+        defaults.layout.name()
+    );
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_info_names_the_version_and_features() {
+        let info = version_info();
+        assert!(info.starts_with(&format!("SanmokuRust {}", env!("CARGO_PKG_VERSION"))));
+        let features = info.lines().find(|l| l.starts_with("Features: ")).unwrap();
+        assert_eq!(features.contains("random"), cfg!(feature = "random"));
+    }
+}
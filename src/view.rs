@@ -0,0 +1,1912 @@
+use crate::clipboard::copy_to_clipboard;
+use crate::collection::{add_to_collection, collection_path, load_collection, save_collection};
+use crate::commentary::{narrate_moves, narrate_result, StandardCommentator, ThemedCommentator};
+use crate::config::{Config, Layout, RenderStyle};
+use crate::dump::dump_state;
+use crate::helpers::{
+    can_win, get_available_cells, is_fork, live_cells, nearest_available, symbol_of_move,
+    winning_line, winning_moves,
+};
+use crate::input::InputBuffer;
+use crate::model::{Board, Cell, Clock, DrawOffer, GameStatus, Message, Model, Player};
+use crate::notation::{parse_notation, to_notation};
+use crate::positions::{random_play_odds, win_probability};
+use crate::puzzles::{is_solution, Puzzle};
+use crate::rules::Rules;
+use crate::solver::{
+    best_move, classify, distance_to_result, first_move_outcome, forced_defensive_moves,
+    game_regret, grade_move, move_scores, opening_equity, position_value, principal_variation,
+    replies_after, MoveGrade, Outcome,
+};
+use crate::strategy::{heuristic_score, traps, Strategy};
+use crate::symmetry::{move_orbits, reflect_horizontal, symmetry_group};
+use crate::theme::Theme;
+use crate::update::{decided_status, update_board_helper};
+use crate::version::version_info;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, stdin, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+// Output goes through `&mut dyn Write` so it can be captured; on stdout a
+// failed write is as fatal as it is for println!.
+const STDOUT_FAILED: &str = "failed printing to stdout";
+
+// The optional reports of each turn. The verbosity levels add up: 1
+// narrates the moves and comments on them, 2 adds the computer's
+// evaluation and the value of every move, 3 the best line and replies.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct TurnReports {
+    pub narration: bool,
+    pub analysis: bool,
+    pub evaluation: bool,
+    pub move_values: bool,
+    pub best_lines: bool,
+}
+
+pub fn turn_reports(config: &Config) -> TurnReports {
+    let verbosity = config.verbosity;
+    TurnReports {
+        // Without a visible board the moves have to be spelled out
+        narration: config.accessible || config.blindfold || verbosity >= 1,
+        analysis: verbosity >= 1,
+        evaluation: config.show_eval || verbosity >= 2,
+        move_values: verbosity >= 2,
+        best_lines: verbosity >= 3,
+    }
+}
+
+pub fn view(model: &Model) -> Message {
+    let reports = turn_reports(&model.config);
+    if model.config.status_line && model.first_player.is_some() {
+        println!("{}", status_line(model));
+    }
+    if reports.narration {
+        announce_moves(model);
+    }
+    if model.config.grade_moves {
+        print_move_grade(model);
+    }
+    if model.config.forced_outcomes && !model.config.arcade() {
+        print_forced_outcome_changes(model);
+    }
+    if reports.evaluation {
+        print_computer_evaluation(model);
+    }
+    if reports.analysis {
+        print_analysis(model);
+    }
+    if model.config.odds && model.first_player.is_some() {
+        print_random_odds(model);
+    }
+    match model.draw_offer {
+        Some(DrawOffer::Accepted) => println!("The computer accepts your draw offer."),
+        Some(DrawOffer::Declined) => println!("The computer declines your draw offer."),
+        _ => (),
+    }
+    if model.swapped {
+        match (model.first_player, model.history.len()) {
+            (Some(Player::Computer), 1) => println!(
+                "The computer swaps sides: your opening move is now its own, and you play {}.",
+                model.user_symbol()
+            ),
+            (Some(Player::User), 2) => {
+                println!("You swap sides and now play {}.", model.user_symbol())
+            }
+            _ => (),
+        }
+    }
+    let mut out = io::stdout();
+    if model.status != GameStatus::NotFinished {
+        write_end_screen(&mut out, model).expect(STDOUT_FAILED);
+        Message::Nothing
+    } else {
+        if model.first_player.is_none() {
+            return select_first_player_view(&model.config.theme);
+        }
+        if model.config.arcade() {
+            write_line_score(&mut out, model).expect(STDOUT_FAILED);
+        }
+        if let Some(clock) = model.clock {
+            write_clock(&mut out, clock).expect(STDOUT_FAILED);
+        }
+        render_board(&mut out, model).expect(STDOUT_FAILED);
+        // Only with --prompt-opening is the computer ever left to move
+        if model.player_of_move(model.history.len()) == Some(Player::Computer) {
+            println!("Press Enter to let the computer start");
+            get_user_input().unwrap_or_else(|| quit_at_end_of_input());
+            return Message::ComputerStart;
+        }
+        let available = model.available_cells();
+        if let (true, [forced]) = (model.config.auto_forced, available) {
+            println!(
+                "Auto-playing forced move at {}",
+                model.config.layout.label_of(*forced)
+            );
+            return Message::CellClicked(*forced);
+        }
+        ask_move(model, available)
+    }
+}
+
+// One line for scripts, e.g. "STATUS board=o___x____ to_move=o
+// status=notfinished legal=1,2,3,5,6,7,8". Cells are internal indices in
+// row order; the status is from the user's side.
+pub fn status_line(model: &Model) -> String {
+    let board: String = model
+        .board
+        .iter()
+        .map(|&cell| match cell {
+            Cell::Nought => 'o',
+            Cell::Cross => 'x',
+            Cell::Unfilled => '_',
+            Cell::Blocked => '#',
+        })
+        .collect();
+    let status = match model.status {
+        GameStatus::NotFinished => "notfinished",
+        GameStatus::Draw => "draw",
+        GameStatus::Settled(Player::User) => "win",
+        GameStatus::Settled(Player::Computer) => "loss",
+    };
+    let legal: Vec<String> = if model.status == GameStatus::NotFinished {
+        model
+            .available_cells()
+            .iter()
+            .map(|i| i.to_string())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    format!(
+        "STATUS board={} to_move={} status={} legal={}",
+        board,
+        model.symbol_of_move(model.history.len()),
+        status,
+        legal.join(",")
+    )
+}
+
+// Comments on the moves played since the user's previous turn.
+pub fn print_analysis(model: &Model) {
+    for k in model.recent_moves_start()..model.history.len() {
+        let cell = model.history[k];
+        let before = model.board_after_moves(k);
+        if !model.config.misere && is_fork(&before, model.symbol_of_move(k), cell) {
+            println!(
+                "Fork created at cell {} — two ways to win",
+                model.config.layout.label_of(cell)
+            );
+        }
+    }
+    if model.status == GameStatus::NotFinished && model.first_player.is_some() {
+        // The user's side here is the one to move, which in solo games
+        // changes every turn
+        let user = model.symbol_of_move(model.history.len());
+        let earlier = model.board_after_moves(model.recent_moves_start());
+        if can_win(&earlier, user) && !can_win(&model.board, user) {
+            println!("No line is left open for you");
+        }
+        if let [cell] = forced_defensive_moves(&model.board, user, model.config.rules())[..] {
+            println!(
+                "Only move: {} — anything else loses",
+                model.config.layout.label_of(cell)
+            );
+        }
+        let reports = turn_reports(&model.config);
+        if reports.move_values {
+            print_move_values(model);
+        }
+        if reports.best_lines {
+            print_principal_variation(model);
+            print_replies(model);
+        }
+    }
+}
+
+// What the user can force after the first `n` moves of the game.
+fn user_outcome_after(model: &Model, n: usize) -> Outcome {
+    let value = position_value(
+        &model.board_after_moves(n),
+        model.symbol_of_move(n),
+        model.config.rules(),
+    );
+    let to_move_is_user = model.player_of_move(n) == Some(Player::User);
+    Outcome::from_value(if to_move_is_user { value } else { -value })
+}
+
+// A comment for each recent move that changed what the user can force.
+pub fn forced_outcome_changes(model: &Model) -> Vec<&'static str> {
+    if model.status != GameStatus::NotFinished {
+        return Vec::new();
+    }
+    (model.recent_moves_start()..model.history.len())
+        .filter_map(|k| {
+            let before = user_outcome_after(model, k);
+            let after = user_outcome_after(model, k + 1);
+            if before == after {
+                return None;
+            }
+            Some(match (before, after) {
+                (Outcome::Win, Outcome::Draw) => "You can no longer win — best case is a draw",
+                (_, Outcome::Loss) => "This is now a forced win for the computer",
+                (_, Outcome::Win) => "You now have a forced win",
+                _ => "The computer can no longer win — it's a draw with best play",
+            })
+        })
+        .collect()
+}
+
+pub fn print_forced_outcome_changes(model: &Model) {
+    for comment in forced_outcome_changes(model) {
+        println!("{}", comment);
+    }
+}
+
+// A friendlier measure than the solver's for casual games.
+pub fn print_random_odds(model: &Model) {
+    let config = &model.config;
+    if model.status != GameStatus::NotFinished || config.arcade() {
+        return;
+    }
+    let to_move = model.symbol_of_move(model.history.len());
+    let odds = random_play_odds(&model.board, to_move, config.rules());
+    println!(
+        "If both sides played at random from here: you win {:.0}%, draw {:.0}%, lose {:.0}%",
+        100.0 * odds.win,
+        100.0 * odds.draw,
+        100.0 * odds.loss
+    );
+}
+
+// Grades the user's latest move against the solver.
+pub fn print_move_grade(model: &Model) {
+    let k = model.recent_moves_start();
+    if model.player_of_move(k) != Some(Player::User) || k >= model.history.len() {
+        return;
+    }
+    let before = model.board_after_moves(k);
+    let after = model.board_after_moves(k + 1);
+    let grade = match grade_move(
+        &before,
+        &after,
+        model.symbol_of_move(k),
+        model.config.rules(),
+    ) {
+        MoveGrade::Optimal => "optimal",
+        MoveGrade::Inaccuracy => "an inaccuracy",
+        MoveGrade::Blunder => "a blunder",
+    };
+    println!(
+        "Your move at {} was {}",
+        model.config.layout.label_of(model.history[k]),
+        grade
+    );
+}
+
+// Why `cell` is not good enough in teach mode, None if it is an optimal move.
+pub fn teach_feedback(
+    board: &Board,
+    symbol: Cell,
+    cell: usize,
+    rules: Rules,
+    layout: Layout,
+) -> Option<String> {
+    let after = update_board_helper(board, cell, symbol);
+    let problem = match grade_move(board, &after, symbol, rules) {
+        MoveGrade::Optimal => return None,
+        MoveGrade::Inaccuracy => "lets a won game slip to a draw",
+        MoveGrade::Blunder => "loses against best play",
+    };
+    let best = best_move(board, symbol, rules)?;
+    Some(format!(
+        "Cell {} {}. Try {}, which {}.",
+        layout.label_of(cell),
+        problem,
+        layout.label_of(best),
+        match position_value(board, symbol, rules) {
+            v if v > 0 => "wins",
+            0 => "holds the draw",
+            _ => "is the toughest defence",
+        }
+    ))
+}
+
+// What the computer saw when it chose its latest move, the chosen cell in
+// brackets. None until the computer has moved.
+pub fn computer_evaluation(model: &Model) -> Option<String> {
+    let k = (model.recent_moves_start()..model.history.len())
+        .find(|&k| model.player_of_move(k) == Some(Player::Computer))?;
+    let chosen = model.history[k];
+    let before = model.board_after_moves(k);
+    let symbol = model.computer_symbol();
+    let rules = model.config.rules();
+    let scores: Vec<(usize, String)> = match model.config.strategy {
+        Strategy::Random | Strategy::WeightedRandom => {
+            return Some("Computer: random move".to_string());
+        }
+        Strategy::Heuristic { aggressiveness } => get_available_cells(&before)
+            .into_iter()
+            .map(|i| {
+                let score = heuristic_score(&before, symbol, i, aggressiveness, rules);
+                (i, format!("{:.1}", score))
+            })
+            .collect(),
+        Strategy::Optimal { .. } | Strategy::Human { .. } => move_scores(&before, symbol, rules)
+            .into_iter()
+            .map(|(i, value)| (i, value_name(value).to_string()))
+            .collect(),
+        Strategy::Annoying => move_scores(&before, symbol, rules)
+            .into_iter()
+            .map(|(i, value)| {
+                let count = traps(&before, symbol, i, rules);
+                (i, format!("{} ({} traps)", value_name(value), count))
+            })
+            .collect(),
+        Strategy::Exploit => get_available_cells(&before)
+            .into_iter()
+            .map(|i| {
+                let mut next = before;
+                next[i] = symbol;
+                (
+                    i,
+                    format!("{:.0}%", 100.0 * win_probability(&next, symbol, rules)),
+                )
+            })
+            .collect(),
+    };
+    let parts: Vec<String> = scores
+        .iter()
+        .map(|(i, score)| {
+            let label = model.config.layout.label_of(*i);
+            if *i == chosen {
+                format!("[{} {}]", label, score)
+            } else {
+                format!("{} {}", label, score)
+            }
+        })
+        .collect();
+    Some(format!("Computer considered: {}", parts.join(", ")))
+}
+
+pub fn print_computer_evaluation(model: &Model) {
+    if let Some(text) = computer_evaluation(model) {
+        println!("{}", text);
+    }
+}
+
+fn value_name(value: i32) -> &'static str {
+    match value {
+        v if v > 0 => "win",
+        0 => "draw",
+        _ => "loss",
+    }
+}
+
+pub fn print_move_values(model: &Model) {
+    let to_move = model.symbol_of_move(model.history.len());
+    let scores = move_scores(&model.board, to_move, model.config.rules());
+    let label = |i: usize| model.config.layout.label_of(i);
+    let parts: Vec<String> = if model.config.group_symmetric {
+        let cells: Vec<usize> = scores.iter().map(|&(i, _)| i).collect();
+        move_orbits(&model.board, &cells)
+            .iter()
+            .map(|orbit| {
+                let value = scores.iter().find(|&&(i, _)| i == orbit[0]).unwrap().1;
+                if orbit.len() > 1 {
+                    format!(
+                        "{} {} ({} equivalent)",
+                        label(orbit[0]),
+                        value_name(value),
+                        orbit.len()
+                    )
+                } else {
+                    format!("{} {}", label(orbit[0]), value_name(value))
+                }
+            })
+            .collect()
+    } else {
+        scores
+            .iter()
+            .map(|&(i, value)| format!("{} {}", label(i), value_name(value)))
+            .collect()
+    };
+    println!("Move values: {}", parts.join(", "));
+}
+
+pub fn print_principal_variation(model: &Model) {
+    let mut board = model.board;
+    let mut to_move = model.symbol_of_move(model.history.len());
+    let rules = model.config.rules();
+    let mut steps = Vec::new();
+    for i in principal_variation(&model.board, to_move, rules) {
+        steps.push(format!(
+            "{} plays {}",
+            to_move,
+            model.config.layout.label_of(i)
+        ));
+        board[i] = to_move;
+        to_move = to_move.opponent();
+    }
+    let result = match winning_line(&board) {
+        Some((a, _, _)) => format!("{} wins", rules.winner(board[a])),
+        None => "draw".to_string(),
+    };
+    steps.push(result);
+    println!("Best play: {}", steps.join(", "));
+}
+
+pub fn print_replies(model: &Model) {
+    let label = |i: usize| model.config.layout.label_of(i);
+    let to_move = model.symbol_of_move(model.history.len());
+    let parts: Vec<String> = replies_after(&model.board, to_move, model.config.rules())
+        .iter()
+        .map(|&(i, reply, value)| format!("{} → {} {}", label(i), label(reply), value_name(value)))
+        .collect();
+    if !parts.is_empty() {
+        println!("Best replies: {}", parts.join(", "));
+    }
+}
+
+// Full evaluation of a position for `sanmoku analyze`.
+pub fn analysis_text(board: &Board, to_move: Cell, rules: Rules, layout: Layout) -> String {
+    let (outcome, moves) = distance_to_result(board, to_move, rules);
+    let result = match outcome {
+        Outcome::Win => format!("win in {}", moves),
+        Outcome::Loss => format!("loss in {}", moves),
+        Outcome::Draw => "draw".to_string(),
+    };
+    let mut lines = vec![
+        format!("{} to move: {}", to_move, result),
+        format!("Position: {}", classify(board, to_move, rules).label()),
+        format!(
+            "Symmetries: {} of 8 rotations and reflections leave it unchanged",
+            symmetry_group(board).len()
+        ),
+    ];
+    let scores = move_scores(board, to_move, rules);
+    if !scores.is_empty() {
+        let parts: Vec<String> = scores
+            .iter()
+            .map(|&(i, value)| format!("{} {}", layout.label_of(i), value_name(value)))
+            .collect();
+        lines.push(format!("Move values: {}", parts.join(", ")));
+        let line: Vec<String> = principal_variation(board, to_move, rules)
+            .iter()
+            .map(|&i| layout.label_of(i).to_string())
+            .collect();
+        lines.push(format!("Best play: {}", line.join(" ")));
+    }
+    lines.join("\n")
+}
+
+// One-line evaluation for `sanmoku batch`.
+pub fn analysis_summary(board: &Board, to_move: Cell, rules: Rules, layout: Layout) -> String {
+    let value = value_name(position_value(board, to_move, rules));
+    match best_move(board, to_move, rules) {
+        Some(i) => format!("{}, best {}", value, layout.label_of(i)),
+        None => value.to_string(),
+    }
+}
+
+// Asks for moves until the puzzle is solved.
+pub fn solve_puzzle(puzzle: &Puzzle, layout: Layout) {
+    let (board, to_move) = match parse_notation(puzzle.position) {
+        Ok(position) => position,
+        Err(_) => return,
+    };
+    print_board(&board, layout);
+    let goal = match puzzle.outcome {
+        Outcome::Win => "wins",
+        Outcome::Draw => "holds the draw",
+        Outcome::Loss => "loses slowest",
+    };
+    println!(
+        "{}: find the move that {} for {} {}",
+        puzzle.title,
+        goal,
+        to_move,
+        layout.label_range()
+    );
+    loop {
+        let s = get_user_input().unwrap_or_else(|| quit_at_end_of_input());
+        let cell = match parse_move(&s, layout) {
+            Some(i) if board[i] == Cell::Unfilled => i,
+            _ => {
+                println!("Please input a free cell {} :", layout.label_range());
+                continue;
+            }
+        };
+        if is_solution(&board, to_move, cell, Rules::Standard) {
+            println!("Correct!");
+            return;
+        }
+        let reply = best_move(
+            &update_board_helper(&board, cell, to_move),
+            to_move.opponent(),
+            Rules::Standard,
+        );
+        match reply {
+            Some(r) => println!(
+                "Not quite — after {} the reply {} spoils it. Try again:",
+                layout.label_of(cell),
+                layout.label_of(r)
+            ),
+            None => println!("Not quite. Try again:"),
+        }
+    }
+}
+
+// False once input has ended, as it does straight away when piped, so
+// the caller can wrap up instead of waiting.
+pub fn wait_for_enter() -> bool {
+    println!("Press Enter to continue...");
+    get_user_input().is_some()
+}
+
+// The answer to "Play again?". Between games the difficulty can also be
+// changed with `diff <strategy>`, taking the names --strategy does.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PlayAgain {
+    Yes,
+    No,
+    ChangeDifficulty(Strategy),
+}
+
+// End of input counts as no.
+pub fn ask_play_again(theme: &Theme) -> PlayAgain {
+    println!("{}", theme.play_again);
+    loop {
+        let s = match get_user_input() {
+            Some(s) => s,
+            None => return PlayAgain::No,
+        };
+        let s = s.trim();
+        if let Some(name) = s.strip_prefix("diff ") {
+            let args = ["--strategy".to_string(), name.trim().to_string()];
+            match Config::from_args(&args) {
+                Ok(config) => return PlayAgain::ChangeDifficulty(config.strategy),
+                Err(e) => println!("{}", e),
+            }
+        } else if s.starts_with('y') {
+            return PlayAgain::Yes;
+        } else if s.starts_with('n') {
+            return PlayAgain::No;
+        } else {
+            println!("{}", theme.yes_no_again);
+        }
+    }
+}
+
+pub fn select_first_player_view(theme: &Theme) -> Message {
+    let do_user_play_first = ask_user_to_be_first(theme);
+    Message::PlayerSelected {
+        user_play_first: do_user_play_first,
+    }
+}
+
+// The board, result banner and post-game notes of a finished game.
+pub fn write_end_screen(out: &mut dyn Write, model: &Model) -> io::Result<()> {
+    let banner = if model.config.solo {
+        solo_result(model)
+    } else {
+        narrate_result(model, &mut ThemedCommentator(&model.config.theme)).unwrap_or_default()
+    };
+    render_board(out, model)?;
+    match model.clock.and_then(|clock| clock.flagged()) {
+        Some(Player::User) => writeln!(out, "Your time ran out.")?,
+        Some(Player::Computer) => writeln!(out, "The computer's time ran out.")?,
+        None => (),
+    }
+    if winning_line(&model.board).is_none()
+        && model.board.contains(&Cell::Unfilled)
+        && decided_status(model) == Some(model.status)
+    {
+        writeln!(out, "No move can change the result any more.")?;
+    }
+    writeln!(out, "======== {} =======", banner)?;
+    if model.config.arcade() {
+        write_line_score(out, model)?;
+    }
+    write_move_numbers(out, model)?;
+    write_missed_wins(out, model)
+}
+
+// Nobody to win or lose against, so only the symbols are named.
+fn solo_result(model: &Model) -> String {
+    let winner = match model.status {
+        GameStatus::Settled(Player::User) => model.user_symbol(),
+        GameStatus::Settled(Player::Computer) => model.computer_symbol(),
+        GameStatus::Draw => return model.config.theme.draw.clone(),
+        GameStatus::NotFinished => return String::new(),
+    };
+    match winner {
+        Cell::Nought => "Nought wins!".to_string(),
+        _ => "Cross wins!".to_string(),
+    }
+}
+
+pub fn render_board(out: &mut dyn Write, model: &Model) -> io::Result<()> {
+    if !model.config.center {
+        return render_board_plain(out, model);
+    }
+    let mut board = Vec::new();
+    render_board_plain(&mut board, model)?;
+    if board.is_empty() {
+        return Ok(());
+    }
+    let width = terminal_width();
+    let padding = "\n".repeat(model.config.padding);
+    write!(out, "{}", padding)?;
+    if let Some(title) = &model.config.title {
+        write!(out, "{}", center_block(title, width))?;
+    }
+    write!(
+        out,
+        "{}{}",
+        center_block(&String::from_utf8_lossy(&board), width),
+        padding
+    )
+}
+
+// Columns of the terminal: $COLUMNS if set, else what stty reports for the
+// terminal on stdin. None when neither knows. Asked once per run, so
+// --center doesn't start stty for every board.
+pub fn terminal_width() -> Option<usize> {
+    static WIDTH: OnceLock<Option<usize>> = OnceLock::new();
+    *WIDTH.get_or_init(query_terminal_width)
+}
+
+fn query_terminal_width() -> Option<usize> {
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|s| s.parse().ok()) {
+        return Some(columns);
+    }
+    let output = process::Command::new("stty")
+        .arg("size")
+        .stdin(process::Stdio::inherit())
+        .output()
+        .ok()?;
+    // "rows columns"
+    String::from_utf8(output.stdout)
+        .ok()?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+// Shifts every line of `text` right by the same amount so its widest line
+// sits in the middle of `width` columns. Left as it is when the width is
+// unknown or too narrow.
+pub fn center_block(text: &str, width: Option<usize>) -> String {
+    let widest = text.lines().map(|line| line.chars().count()).max();
+    let indent = match (width, widest) {
+        (Some(width), Some(widest)) => " ".repeat(width.saturating_sub(widest) / 2),
+        _ => String::new(),
+    };
+    text.lines()
+        .map(|line| format!("{}{}\n", indent, line))
+        .collect()
+}
+
+fn render_board_plain(out: &mut dyn Write, model: &Model) -> io::Result<()> {
+    // Blindfold games only reveal the board once they are over
+    if model.config.blindfold && model.status == GameStatus::NotFinished {
+        return Ok(());
+    }
+    let marks = Marks {
+        last: if model.config.highlight_last {
+            model.history.last().copied()
+        } else {
+            None
+        },
+        ghost: ghost_cell(model),
+        dead: if model.config.live_cells {
+            dead_cells(&model.board)
+        } else {
+            [false; 9]
+        },
+    };
+    if model.config.accessible {
+        describe_board(out, &model.board, model.config.layout)
+    } else if model.config.coords {
+        writeln!(
+            out,
+            "{}",
+            render_coordinates(&model.board, model.config.layout, marks)
+        )
+    } else {
+        writeln!(
+            out,
+            "{}",
+            render_styled(
+                &model.board,
+                model.config.layout,
+                model.config.render_style,
+                marks
+            )
+        )
+    }
+}
+
+// Cells drawn specially. The cell played last shows in capitals, or in
+// brackets in the padded styles; the ghost, an empty cell, as `?`; dead
+// empty cells as `-`.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Marks {
+    pub last: Option<usize>,
+    pub ghost: Option<usize>,
+    pub dead: [bool; 9],
+}
+
+impl Marks {
+    // Empty cells drawn as something other than their label or a blank.
+    fn hides(&self, i: usize) -> bool {
+        self.ghost == Some(i) || self.dead[i]
+    }
+}
+
+// Empty cells that no line can be won through any more.
+pub fn dead_cells(board: &Board) -> [bool; 9] {
+    let live = live_cells(board);
+    let mut dead = [false; 9];
+    for i in get_available_cells(board) {
+        dead[i] = !live.contains(&i);
+    }
+    dead
+}
+
+// The computer's best move if it were its turn, shown under --ghost while
+// the user thinks.
+pub fn ghost_cell(model: &Model) -> Option<usize> {
+    let users_turn = model.player_of_move(model.history.len()) == Some(Player::User);
+    if model.config.ghost && users_turn && model.status == GameStatus::NotFinished {
+        best_move(&model.board, model.computer_symbol(), model.config.rules())
+    } else {
+        None
+    }
+}
+
+pub fn render_styled(board: &Board, layout: Layout, style: RenderStyle, marks: Marks) -> String {
+    match style {
+        RenderStyle::Compact => render_compact(board, layout, marks),
+        RenderStyle::Standard => render_standard(board, layout, marks),
+        RenderStyle::Spacious => render_spacious(board, layout, marks),
+        RenderStyle::Unicode => render_unicode(board, layout, marks),
+    }
+}
+
+fn mark(board: &Board, i: usize, marks: Marks) -> String {
+    if marks.last == Some(i) {
+        board[i].to_string().to_uppercase()
+    } else if marks.ghost == Some(i) {
+        "?".to_string()
+    } else if marks.dead[i] {
+        "-".to_string()
+    } else {
+        board[i].to_string()
+    }
+}
+
+fn padded_mark(board: &Board, i: usize, marks: Marks) -> String {
+    if marks.last == Some(i) {
+        format!("[{}]", board[i])
+    } else if marks.ghost == Some(i) {
+        "(?)".to_string()
+    } else if marks.dead[i] {
+        " - ".to_string()
+    } else {
+        format!(" {} ", board[i])
+    }
+}
+
+// The board alone, with letters over the columns and numbers beside the
+// rows, for moves given as coordinates.
+pub fn render_coordinates(board: &Board, layout: Layout, marks: Marks) -> String {
+    let mut lines = vec!["  a b c".to_string()];
+    for row in 0..3 {
+        let cells: Vec<String> = display_row(layout, row)
+            .iter()
+            .map(|&i| mark(board, i, marks))
+            .collect();
+        lines.push(format!("{} {}", row + 1, cells.join("|")));
+    }
+    lines.join("\n")
+}
+
+// Cells of display row `row` in drawing order.
+fn display_row(layout: Layout, row: usize) -> [usize; 3] {
+    [0, 1, 2].map(|col| layout.cell_at(row * 3 + col))
+}
+
+pub fn render_compact(board: &Board, layout: Layout, marks: Marks) -> String {
+    (0..3)
+        .map(|row| {
+            display_row(layout, row)
+                .iter()
+                .map(|&i| match board[i] {
+                    Cell::Unfilled if !marks.hides(i) => layout.label_of(i).to_string(),
+                    _ => mark(board, i, marks),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn render_standard(board: &Board, layout: Layout, marks: Marks) -> String {
+    (0..3)
+        .map(|row| {
+            let [a, b, c] = display_row(layout, row);
+            format!(
+                "{}|{}|{}  {}|{}|{}",
+                layout.label_of(a),
+                layout.label_of(b),
+                layout.label_of(c),
+                mark(board, a, marks),
+                mark(board, b, marks),
+                mark(board, c, marks)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// The label guide and the board side by side, each cell padded to three
+// columns and the rows separated by rules.
+pub fn render_spacious(board: &Board, layout: Layout, marks: Marks) -> String {
+    let rows: Vec<String> = (0..3)
+        .map(|row| {
+            let cells = display_row(layout, row);
+            let labels: Vec<String> = cells
+                .iter()
+                .map(|&i| format!(" {} ", layout.label_of(i)))
+                .collect();
+            let symbols: Vec<String> = cells
+                .iter()
+                .map(|&i| padded_mark(board, i, marks))
+                .collect();
+            format!("{}    {}", labels.join("|"), symbols.join("|"))
+        })
+        .collect();
+    rows.join("\n---+---+---    ---+---+---\n")
+}
+
+// Box-drawing grid. Empty cells show their label so the board doubles as
+// the move guide.
+pub fn render_unicode(board: &Board, layout: Layout, marks: Marks) -> String {
+    let row_text = |row: usize| {
+        let cells: Vec<String> = (row * 3..row * 3 + 3)
+            .map(|p| layout.cell_at(p))
+            .map(|i| match board[i] {
+                Cell::Unfilled if !marks.hides(i) => format!(" {} ", layout.label_of(i)),
+                _ => padded_mark(board, i, marks),
+            })
+            .collect();
+        format!("│{}│", cells.join("│"))
+    };
+    [
+        "┌───┬───┬───┐".to_string(),
+        row_text(0),
+        "├───┼───┼───┤".to_string(),
+        row_text(1),
+        "├───┼───┼───┤".to_string(),
+        row_text(2),
+        "└───┴───┴───┘".to_string(),
+    ]
+    .join("\n")
+}
+
+// Board showing the order cells were played in: the first player's moves as
+// plain numbers, the second player's in parentheses.
+pub fn render_with_move_numbers(start: &Board, history: &[usize]) -> String {
+    let mut cells: Vec<String> = start.iter().map(|&cell| format!(" {} ", cell)).collect();
+    for (k, &i) in history.iter().enumerate() {
+        cells[i] = if k.is_multiple_of(2) {
+            format!("{:^3}", k + 1)
+        } else {
+            format!("({})", k + 1)
+        };
+    }
+    cells
+        .chunks(3)
+        .map(|row| row.join("|"))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn write_move_numbers(out: &mut dyn Write, model: &Model) -> io::Result<()> {
+    if model.config.move_numbers {
+        let layout = model.config.layout;
+        let mut start = Model::initial_board(&model.config);
+        let mut history = model.history.clone();
+        if layout.is_mirrored() {
+            start = reflect_horizontal(&start);
+            history = history.iter().map(|&i| layout.cell_at(i)).collect();
+        }
+        writeln!(out, "Move order:")?;
+        writeln!(out, "{}", render_with_move_numbers(&start, &history))?;
+    }
+    Ok(())
+}
+
+// The user's turns (0-based move index) where they could have won on the
+// spot but played elsewhere, with one of the winning cells. Under misère
+// no move wins on the spot.
+pub fn missed_wins(model: &Model) -> Vec<(usize, usize)> {
+    if model.config.misere {
+        return Vec::new();
+    }
+    (0..model.history.len())
+        .filter(|&k| model.player_of_move(k) == Some(Player::User))
+        .filter_map(|k| {
+            let wins = winning_moves(&model.board_after_moves(k), model.symbol_of_move(k));
+            match wins.first() {
+                Some(&win) if !wins.contains(&model.history[k]) => Some((k, win)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+// Post-game coaching, shown with --grade or any verbosity.
+pub fn write_missed_wins(out: &mut dyn Write, model: &Model) -> io::Result<()> {
+    if !model.config.grade_moves && model.config.verbosity == 0 {
+        return Ok(());
+    }
+    for (k, cell) in missed_wins(model) {
+        writeln!(
+            out,
+            "Missed win at move {}: you could have played cell {}",
+            k + 1,
+            model.config.layout.label_of(cell)
+        )?;
+    }
+    // Regret replays the game from the empty board
+    let config = &model.config;
+    if !config.arcade() && config.blocked.is_empty() && config.position.is_none() {
+        writeln!(
+            out,
+            "Regret: {}",
+            game_regret(
+                &model.history,
+                |k| model.player_of_move(k) == Some(Player::User),
+                config.rules()
+            )
+        )?;
+    }
+    Ok(())
+}
+
+pub fn write_clock(out: &mut dyn Write, clock: Clock) -> io::Result<()> {
+    let time = |left: Duration| format!("{}:{:02}", left.as_secs() / 60, left.as_secs() % 60);
+    writeln!(
+        out,
+        "Clock: you {}, computer {}",
+        time(clock.user),
+        time(clock.computer)
+    )
+}
+
+pub fn write_line_score(out: &mut dyn Write, model: &Model) -> io::Result<()> {
+    writeln!(
+        out,
+        "Lines: you {}, computer {}",
+        model.line_score.user, model.line_score.computer
+    )
+}
+
+pub fn write_board(out: &mut dyn Write, board: &Board, layout: Layout) -> io::Result<()> {
+    writeln!(out, "{}", render_standard(board, layout, Marks::default()))
+}
+
+pub fn print_board(board: &Board, layout: Layout) {
+    write_board(&mut io::stdout(), board, layout).expect(STDOUT_FAILED);
+}
+
+// What replay shows after each move of a game: the move, then the board.
+pub fn replay_frames(moves: &[usize], layout: Layout) -> Vec<String> {
+    let mut board = [Cell::Unfilled; 9];
+    let mut frames = Vec::with_capacity(moves.len());
+    for (k, &i) in moves.iter().enumerate() {
+        board[i] = symbol_of_move(k);
+        let mut frame = format!(
+            "Move {}: {} plays {}\n",
+            k + 1,
+            board[i],
+            layout.label_of(i)
+        );
+        frame.push_str(&render_standard(&board, layout, Marks::default()));
+        frame.push('\n');
+        frames.push(frame);
+    }
+    frames
+}
+
+pub fn cell_name(cell: Cell) -> &'static str {
+    match cell {
+        Cell::Nought => "nought",
+        Cell::Cross => "cross",
+        Cell::Unfilled => "empty",
+        Cell::Blocked => "blocked",
+    }
+}
+
+// Linear description of the board for screen readers.
+pub fn describe_board(out: &mut dyn Write, board: &Board, layout: Layout) -> io::Result<()> {
+    let mut cells: Vec<(usize, Cell)> = board
+        .iter()
+        .enumerate()
+        .map(|(i, &cell)| (layout.label_of(i), cell))
+        .collect();
+    cells.sort_by_key(|&(label, _)| label);
+    let parts: Vec<String> = cells
+        .iter()
+        .map(|&(label, cell)| format!("Cell {}: {}", label, cell_name(cell)))
+        .collect();
+    writeln!(out, "{}.", parts.join(", "))
+}
+
+pub fn announce_moves(model: &Model) {
+    for line in narrate_moves(model, &mut StandardCommentator) {
+        println!("{}", line);
+    }
+}
+
+thread_local! {
+    // Stdin unless read_input_from says otherwise
+    static INPUT: RefCell<InputBuffer> =
+        RefCell::new(InputBuffer::new(Box::new(BufReader::new(stdin()))));
+}
+
+// Reads all further input from `path`, typically a named pipe another
+// program writes moves into. Opening a pipe waits for its writer, and the
+// writer closing it ends the input.
+pub fn read_input_from(path: &Path) -> io::Result<()> {
+    let file = File::open(path)?;
+    read_input_with(Box::new(BufReader::new(file)));
+    Ok(())
+}
+
+// Reads all further input on this thread from `reader`.
+pub fn read_input_with(reader: Box<dyn BufRead>) {
+    INPUT.with(|input| *input.borrow_mut() = InputBuffer::new(reader));
+}
+
+// None once input has ended (or can't be read).
+pub fn get_user_input() -> Option<String> {
+    INPUT
+        .with(|input| input.borrow_mut().next_line())
+        .ok()
+        .flatten()
+}
+
+// Nothing more can be asked once input has ended, so stop the program.
+fn quit_at_end_of_input() -> ! {
+    println!("End of input, quitting.");
+    process::exit(0)
+}
+
+// End of input counts as no.
+pub fn ask_yes_no(question: &str, theme: &Theme) -> bool {
+    answer_yes_no(question, theme).unwrap_or(false)
+}
+
+// As ask_yes_no, but None at the end of input, for callers that must tell
+// a real no from nobody answering.
+pub fn answer_yes_no(question: &str, theme: &Theme) -> Option<bool> {
+    println!("{} [y/n]: ", question);
+    loop {
+        match get_user_input().as_deref() {
+            None => return None,
+            Some(s) if s.starts_with('y') => return Some(true),
+            Some(s) if s.starts_with('n') => return Some(false),
+            _ => println!("{}", theme.yes_no_again),
+        }
+    }
+}
+
+// Numbered choices; Enter (or the end of input) takes `default`, an index
+// into `options`.
+pub fn ask_menu(question: &str, options: &[&str], default: usize) -> usize {
+    println!("{}", question);
+    for (n, option) in options.iter().enumerate() {
+        let marker = if n == default { " (default)" } else { "" };
+        println!("  {}. {}{}", n + 1, option, marker);
+    }
+    loop {
+        let input = match get_user_input() {
+            Some(input) => input,
+            None => return default,
+        };
+        match input.trim() {
+            "" => return default,
+            s => match s.parse::<usize>() {
+                Ok(n) if (1..=options.len()).contains(&n) => return n - 1,
+                _ => println!(
+                    "Please input 1 to {}, or Enter for the default:",
+                    options.len()
+                ),
+            },
+        }
+    }
+}
+
+// Guided first-run setup. Returns the chosen settings as command-line
+// arguments, leaving out the defaults.
+pub fn setup_wizard() -> Vec<String> {
+    println!("Welcome! A few questions to set up the game; press Enter to keep the default.");
+    let mut args = Vec::new();
+    // Without the random feature the computer always plays perfectly
+    if cfg!(feature = "random") {
+        let strategies = ["random", "weighted", "heuristic", "optimal"];
+        let difficulty = ask_menu(
+            "Difficulty:",
+            &[
+                "easy (random moves)",
+                "medium (random, favouring good cells)",
+                "hard (heuristic)",
+                "perfect play",
+            ],
+            0,
+        );
+        if difficulty != 0 {
+            args.extend(["--strategy".to_string(), strategies[difficulty].to_string()]);
+        }
+    }
+    // Nought always moves first, so this also picks the user's symbol
+    match ask_menu(
+        "Who goes first?",
+        &[
+            "ask before every game",
+            "you, playing o",
+            "the computer, you play x",
+        ],
+        0,
+    ) {
+        1 => args.extend(["--first".to_string(), "user".to_string()]),
+        2 => args.extend(["--first".to_string(), "computer".to_string()]),
+        _ => (),
+    }
+    if ask_menu(
+        "Cell numbers:",
+        &["0-8 from the top left", "1-9 like a numeric keypad"],
+        0,
+    ) == 1
+    {
+        args.extend(["--layout".to_string(), "numpad".to_string()]);
+    }
+    println!("Board: 3x3, the only size available.");
+    args
+}
+
+pub fn ask_user_to_be_first(theme: &Theme) -> bool {
+    println!("{}", theme.first_player);
+    loop {
+        let s = get_user_input().unwrap_or_else(|| quit_at_end_of_input());
+        let first_letter = s.get(0..1);
+        match first_letter {
+            Some("y") => return true,
+            Some("n") => return false,
+            _ => println!("{}", theme.yes_no_again),
+        }
+    }
+}
+
+// Names accepted in place of a cell number, mapped to the position they
+// name on screen.
+pub const MOVE_ALIASES: [(&str, usize); 14] = [
+    ("tl", 0),
+    ("t", 1),
+    ("top", 1),
+    ("tr", 2),
+    ("l", 3),
+    ("left", 3),
+    ("c", 4),
+    ("center", 4),
+    ("r", 5),
+    ("right", 5),
+    ("bl", 6),
+    ("b", 7),
+    ("bottom", 7),
+    ("br", 8),
+];
+
+// Display position of a coordinate like "b2": column a-c from the left,
+// then row 1-3 from the top. None for anything off the board.
+pub fn parse_coordinate(word: &str) -> Option<usize> {
+    let mut chars = word.chars();
+    let (column, row) = (chars.next()?, chars.next()?);
+    if chars.next().is_some() {
+        return None;
+    }
+    let column = "abc".find(column)?;
+    let row = "123".find(row)?;
+    Some(row * 3 + column)
+}
+
+// Internal index of the cell named by the user's input.
+pub fn parse_move(input: &str, layout: Layout) -> Option<usize> {
+    let word = input.trim().to_lowercase();
+    if let Some(&(_, position)) = MOVE_ALIASES.iter().find(|&&(alias, _)| alias == word) {
+        return Some(layout.cell_at(position));
+    }
+    if let Some(position) = parse_coordinate(&word) {
+        return Some(layout.cell_at(position));
+    }
+    let label = word.get(0..1).and_then(|s| s.parse::<usize>().ok())?;
+    layout.index_of(label)
+}
+
+// The user's guess at the computer's next move; blank input skips the guess.
+pub fn ask_prediction(model: &Model) -> Option<usize> {
+    let layout = model.config.layout;
+    let available = model.available_cells();
+    println!("Where will the computer play? {}: ", layout.label_range());
+    loop {
+        let s = get_user_input().unwrap_or_else(|| quit_at_end_of_input());
+        if s.trim().is_empty() {
+            return None;
+        }
+        match parse_move(&s, layout) {
+            Some(i) if available.contains(&i) => return Some(i),
+            _ => println!("Please input a free cell {} :", layout.label_range()),
+        }
+    }
+}
+
+pub fn reveal_prediction(model: &Model, guess: usize, actual: usize) {
+    let layout = model.config.layout;
+    if guess == actual {
+        println!("You predicted {} — correct!", layout.label_of(actual));
+    } else {
+        println!(
+            "You predicted {}, but the computer played {}",
+            layout.label_of(guess),
+            layout.label_of(actual)
+        );
+    }
+}
+
+// Rules of the game as currently configured.
+pub fn rules_text(model: &Model) -> String {
+    let config = &model.config;
+    let mut lines = vec!["Board: 3x3, three in a row to finish".to_string()];
+    lines.push(if config.misere {
+        "Rules: misère — whoever completes a line loses".to_string()
+    } else {
+        "Rules: normal — whoever completes a line wins".to_string()
+    });
+    if let Some((board, to_move)) = config.position {
+        lines.push(format!(
+            "Starting position: {}",
+            to_notation(&board, to_move)
+        ));
+    }
+    if !config.blocked.is_empty() {
+        let cells: Vec<String> = config
+            .blocked
+            .iter()
+            .map(|&i| config.layout.label_of(i).to_string())
+            .collect();
+        lines.push(format!("Blocked cells: {}", cells.join(", ")));
+    }
+    if config.pie {
+        lines.push("Pie rule: after the opening move the other player may swap sides".to_string());
+    }
+    if config.solo {
+        lines.push("Solo: you place both symbols in turn, the computer never plays".to_string());
+    } else {
+        lines.push(match model.first_player {
+            Some(Player::User) => format!("First player: you ({})", model.symbol_of_move(0)),
+            Some(Player::Computer) => {
+                format!("First player: computer ({})", model.symbol_of_move(0))
+            }
+            None => "First player: chosen at the start of each game".to_string(),
+        });
+        lines.push(match config.strategy {
+            Strategy::Random => "Computer: random moves".to_string(),
+            Strategy::WeightedRandom => {
+                "Computer: random moves, favouring the center and corners".to_string()
+            }
+            Strategy::Heuristic { aggressiveness } => {
+                format!("Computer: heuristic, aggressiveness {:.1}", aggressiveness)
+            }
+            Strategy::Optimal { .. } => "Computer: perfect play".to_string(),
+            Strategy::Human { error_rate } => format!(
+                "Computer: near-perfect play, {:.0}% chance of a harmless slip",
+                100.0 * error_rate
+            ),
+            Strategy::Annoying => {
+                "Computer: perfect play, setting as many traps as it can".to_string()
+            }
+            Strategy::Exploit => {
+                "Computer: plays for the best odds against random moves".to_string()
+            }
+        });
+    }
+    // The solver only knows the standard game from the empty board
+    if !config.misere && config.blocked.is_empty() && config.position.is_none() {
+        lines.push(first_move_text(first_move_outcome()));
+        lines.push(opening_equity_text(config.layout));
+    }
+    lines.push(format!(
+        "Cells are numbered {} ({} layout)",
+        config.layout.label_range(),
+        config.layout.name()
+    ));
+    lines.join("\n")
+}
+
+// e.g. "Openings: corner (0) draw, edge (1) draw, center (4) draw".
+pub fn opening_equity_text(layout: Layout) -> String {
+    let openings: Vec<String> = opening_equity()
+        .into_iter()
+        .map(|(i, outcome)| {
+            let kind = match i {
+                4 => "center",
+                _ if i.is_multiple_of(2) => "corner",
+                _ => "edge",
+            };
+            format!("{} ({}) {}", kind, layout.label_of(i), outcome.name())
+        })
+        .collect();
+    format!("Openings: {}", openings.join(", "))
+}
+
+pub fn first_move_text(outcome: Outcome) -> String {
+    match outcome {
+        Outcome::Win => "Going first: a win with perfect play".to_string(),
+        Outcome::Draw => {
+            "Going first: a draw with perfect play, but the first player never loses".to_string()
+        }
+        Outcome::Loss => "Going first: a loss with perfect play".to_string(),
+    }
+}
+
+// Puts the position's notation on the clipboard so it can be pasted into
+// analysis later, or prints it when there is no clipboard.
+pub fn copy_position(model: &Model) {
+    let notation = position_notation(model);
+    match copy_to_clipboard(&notation) {
+        Ok(()) => println!("Copied \"{}\" to the clipboard", notation),
+        Err(_) => println!("Position: {}", notation),
+    }
+}
+
+// The current position with whoever moves next, whichever player that is.
+pub fn position_notation(model: &Model) -> String {
+    to_notation(&model.board, model.symbol_of_move(model.history.len()))
+}
+
+pub fn move_prompt(theme: &Theme, layout: Layout) -> String {
+    theme.move_prompt(layout.label_range())
+}
+
+// Adds the current position to the collection file under `label`.
+pub fn bookmark_position(model: &Model, label: &str) {
+    let path = match collection_path() {
+        Some(path) => path,
+        None => {
+            println!("Nowhere to keep the collection");
+            return;
+        }
+    };
+    let mut bookmarks = match load_collection(&path) {
+        Ok(bookmarks) => bookmarks,
+        Err(e) => {
+            println!("Failed to read {}: {}", path.display(), e);
+            return;
+        }
+    };
+    if !add_to_collection(&mut bookmarks, &model.board, label) {
+        println!("An equivalent position is already in your collection");
+    } else if let Err(e) = save_collection(&path, &bookmarks) {
+        println!("Failed to write {}: {}", path.display(), e);
+    } else {
+        println!("Bookmarked as \"{}\"", label);
+    }
+}
+
+// Besides a cell, the user can enter a prompt command: `copy`, `b <label>`
+// (bookmark the position), `i` (rules and settings), `?` (dump the state),
+// `d` (offer a draw), `s` (swap under the pie rule) or `n` (new game).
+pub fn ask_move(model: &Model, available: &[usize]) -> Message {
+    let layout = model.config.layout;
+    let can_swap = model.swap_available() && model.player_of_move(1) == Some(Player::User);
+    if can_swap {
+        println!("Pie rule: type s to swap sides and take over the opening move.");
+    }
+    let theme = &model.config.theme;
+    let prompt = move_prompt(theme, layout);
+    println!("{}", prompt);
+    loop {
+        let s = get_user_input().unwrap_or_else(|| quit_at_end_of_input());
+        match s.trim() {
+            "copy" => {
+                copy_position(model);
+                continue;
+            }
+            "i" => {
+                println!("{}", rules_text(model));
+                continue;
+            }
+            "?version" => {
+                println!("{}", version_info());
+                continue;
+            }
+            command if command.starts_with("b ") => {
+                bookmark_position(model, command[2..].trim());
+                continue;
+            }
+            "?" => {
+                // stderr, so the dump stays out of the game's own output
+                match dump_state(model) {
+                    Ok(json) => eprintln!("{}", json),
+                    Err(e) => eprintln!("Can't dump the state: {}", e),
+                }
+                continue;
+            }
+            "d" => return Message::DrawOffer,
+            "s" if can_swap => return Message::Swap,
+            "n" => {
+                if ask_yes_no("Abandon this game and start a new one?", theme) {
+                    return Message::NewGame;
+                }
+                println!("{}", prompt);
+                continue;
+            }
+            _ => (),
+        }
+        if let Some(i) = parse_move(&s, layout) {
+            if available.contains(&i) {
+                let feedback = if model.config.teach {
+                    let symbol = model.symbol_of_move(model.history.len());
+                    teach_feedback(&model.board, symbol, i, model.config.rules(), layout)
+                } else {
+                    None
+                };
+                match feedback {
+                    Some(explanation) => {
+                        println!("{}", explanation);
+                        continue;
+                    }
+                    None => return Message::CellClicked(i),
+                }
+            }
+            match nearest_available(i, available) {
+                Some(j) => println!(
+                    "{}",
+                    theme.cell_taken(layout.label_of(i), layout.label_of(j))
+                ),
+                None => println!("{}", theme.cell_unavailable(layout.label_of(i))),
+            }
+        }
+        println!("{}", theme.move_again(layout.label_range()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameBuilder;
+    use crate::config::Config;
+    use crate::helpers::board_after;
+    use crate::update::{apply_moves, update};
+
+    fn rendered(model: &Model) -> String {
+        let mut out = Vec::new();
+        render_board(&mut out, model).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn empty_board_renders_the_label_guide() {
+        let model = Model::new(Config::default());
+        let guide = "0|1|2   | | \n3|4|5   | | \n6|7|8   | | \n";
+        assert_eq!(rendered(&model), guide);
+        let mut out = Vec::new();
+        write_board(&mut out, &model.board, model.config.layout).unwrap();
+        assert_eq!(out, guide.as_bytes());
+    }
+
+    #[test]
+    fn blindfold_board_stays_hidden_during_play() {
+        let config = Config {
+            blindfold: true,
+            ..Config::default()
+        };
+        assert_eq!(rendered(&Model::new(config)), "");
+    }
+
+    #[test]
+    fn block_is_centered_on_its_widest_line() {
+        let text = "0|1|2\n-----\n";
+        assert_eq!(
+            center_block(text, Some(40)),
+            format!("{0}0|1|2\n{0}-----\n", " ".repeat(17))
+        );
+        assert_eq!(center_block("abcd\nab", Some(10)), "   abcd\n   ab\n");
+    }
+
+    #[test]
+    fn block_stays_left_without_room_or_a_width() {
+        assert_eq!(center_block("0|1|2\nab", None), "0|1|2\nab\n");
+        assert_eq!(center_block("0|1|2", Some(3)), "0|1|2\n");
+    }
+
+    fn solo_game() -> Model {
+        let config = Config {
+            solo: true,
+            ..Config::default()
+        };
+        GameBuilder::from_config(config)
+            .first_player(Player::User)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn pasted_moves_are_played_one_per_prompt() {
+        read_input_with(Box::new(io::Cursor::new("4\n0\n8\n")));
+        let mut model = solo_game();
+        for _ in 0..3 {
+            let message = view(&model);
+            model = update(model, message);
+        }
+        assert_eq!(model.history, vec![4, 0, 8]);
+        assert_eq!(get_user_input(), None);
+    }
+
+    #[test]
+    fn end_screen_uses_the_theme_wording() {
+        let theme = Theme::default()
+            .parse("win = Well played\ndraw = Nobody won")
+            .unwrap();
+        let config = Config {
+            theme,
+            ..Config::default()
+        };
+        let mut model = Model::new(config);
+        model.first_player = Some(Player::User);
+        for (status, banner) in [
+            (GameStatus::Settled(Player::User), "Well played"),
+            (GameStatus::Draw, "Nobody won"),
+            (
+                GameStatus::Settled(Player::Computer),
+                "You lose, too bad! Try again!",
+            ),
+        ] {
+            model.status = status;
+            let mut out = Vec::new();
+            write_end_screen(&mut out, &model).unwrap();
+            let text = String::from_utf8(out).unwrap();
+            assert!(
+                text.contains(&format!("======== {} =======", banner)),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn copied_position_has_the_side_to_move() {
+        let mut model = solo_game();
+        for &i in &[4, 0, 8] {
+            model = update(model, Message::CellClicked(i));
+        }
+        let (board, to_move) = parse_notation(&position_notation(&model)).unwrap();
+        assert_eq!(board, model.board);
+        assert_eq!(to_move, Cell::Cross);
+    }
+
+    #[test]
+    fn end_confirmation_returns_at_the_end_of_input() {
+        let path = std::env::temp_dir().join(format!("sanmoku-empty-{}", std::process::id()));
+        std::fs::write(&path, "").unwrap();
+        read_input_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(!wait_for_enter());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn moves_come_through_a_named_pipe_until_the_writer_closes_it() {
+        let path = std::env::temp_dir().join(format!("sanmoku-fifo-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let made = std::process::Command::new("mkfifo").arg(&path).status();
+        assert!(made.unwrap().success());
+        let writer_path = path.clone();
+        let writer = std::thread::spawn(move || {
+            let mut pipe = std::fs::OpenOptions::new()
+                .write(true)
+                .open(writer_path)
+                .unwrap();
+            pipe.write_all(b"4\n8\n").unwrap();
+        });
+        read_input_from(&path).unwrap();
+        writer.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(get_user_input().as_deref(), Some("4\n"));
+        assert_eq!(get_user_input().as_deref(), Some("8\n"));
+        assert_eq!(get_user_input(), None);
+    }
+
+    #[test]
+    fn piped_wizard_keeps_every_default() {
+        read_input_with(Box::new(io::Cursor::new("")));
+        assert!(setup_wizard().is_empty());
+        // Numpad numbering, the last question, after the defaults
+        let answers = if cfg!(feature = "random") {
+            "\n\n2\n"
+        } else {
+            "\n2\n"
+        };
+        read_input_with(Box::new(io::Cursor::new(answers)));
+        let args = setup_wizard();
+        assert!(args.iter().any(|a| a == "--layout"));
+        assert!(!args.iter().any(|a| a == "--first"));
+    }
+
+    #[test]
+    fn difficulty_change_carries_into_the_next_game() {
+        read_input_with(Box::new(io::Cursor::new("diff optimal\n")));
+        let strategy = match ask_play_again(&Theme::default()) {
+            PlayAgain::ChangeDifficulty(strategy) => strategy,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(strategy.name(), "optimal");
+        let mut config = Config {
+            adaptive: true,
+            ..Config::default()
+        };
+        config.change_difficulty(strategy);
+        let next = GameBuilder::from_config(config).build().unwrap();
+        assert_eq!(next.config.strategy, strategy);
+        assert!(!next.config.adaptive);
+    }
+
+    #[test]
+    fn cells_on_blocked_lines_only_are_dead() {
+        let mut board = [Cell::Unfilled; 9];
+        board[0] = Cell::Blocked;
+        board[4] = Cell::Blocked;
+        // The bottom row and the right column are the lines left
+        assert_eq!(live_cells(&board), vec![2, 5, 6, 7, 8]);
+        let dead = dead_cells(&board);
+        assert_eq!((0..9).filter(|&i| dead[i]).collect::<Vec<_>>(), vec![1, 3]);
+        board[8] = Cell::Blocked;
+        assert!(live_cells(&board).is_empty());
+        let dead = dead_cells(&board);
+        assert_eq!(
+            (0..9).filter(|&i| dead[i]).collect::<Vec<_>>(),
+            vec![1, 2, 3, 5, 6, 7]
+        );
+    }
+
+    // The user plays o against the computer, moves taken as given.
+    fn game_of(moves: &[usize]) -> Model {
+        let model = GameBuilder::new()
+            .first_player(Player::User)
+            .build()
+            .unwrap();
+        apply_moves(model, moves).unwrap()
+    }
+
+    #[test]
+    fn missed_wins_points_at_the_winning_cell() {
+        // With 0 and 1 on the top row, o played 5 instead of 2
+        let model = game_of(&[0, 4, 1, 8, 5]);
+        assert_eq!(missed_wins(&model), vec![(4, 2)]);
+        let model = game_of(&[0, 4, 1, 8, 2]);
+        assert!(missed_wins(&model).is_empty());
+    }
+
+    #[test]
+    fn ghost_is_the_computers_best_move() {
+        let mut model = game_of(&[0, 4, 1]);
+        model.config.ghost = true;
+        // Only shown while the user thinks
+        assert_eq!(ghost_cell(&model), None);
+        let model = apply_moves(model, &[2]).unwrap();
+        let best = best_move(&model.board, Cell::Cross, Rules::Standard);
+        assert_eq!(best, Some(6));
+        assert_eq!(ghost_cell(&model), best);
+    }
+
+    #[test]
+    fn forced_outcome_comments_only_on_a_change() {
+        // o's corner and x's edge reply: o can now force a win
+        let model = game_of(&[0, 1]);
+        assert_eq!(
+            forced_outcome_changes(&model),
+            vec!["You now have a forced win"]
+        );
+        // Taking the center keeps it, so there is nothing new to say
+        let model = apply_moves(model, &[4, 8]).unwrap();
+        assert!(forced_outcome_changes(&model).is_empty());
+        // Blocking instead of forking lets it go
+        let model = apply_moves(model, &[2]).unwrap();
+        assert_eq!(
+            forced_outcome_changes(&model),
+            vec!["You can no longer win — best case is a draw"]
+        );
+    }
+
+    #[test]
+    fn teach_mode_suggests_the_block() {
+        let (board, to_move) = parse_notation("oo./.x./... x").unwrap();
+        let feedback =
+            |cell, layout| teach_feedback(&board, to_move, cell, Rules::Standard, layout);
+        assert_eq!(feedback(2, Layout::Standard), None);
+        assert_eq!(
+            feedback(8, Layout::Standard).as_deref(),
+            Some("Cell 8 loses against best play. Try 2, which holds the draw.")
+        );
+        // Cells are named as the layout labels them
+        assert_eq!(
+            feedback(8, Layout::Numpad).as_deref(),
+            Some("Cell 3 loses against best play. Try 9, which holds the draw.")
+        );
+    }
+
+    #[test]
+    fn aliases_name_the_center_and_corners() {
+        let standard = |input| parse_move(input, Layout::Standard);
+        assert_eq!(standard("center"), Some(4));
+        assert_eq!(standard(" C "), Some(4));
+        assert_eq!(standard("tl"), Some(0));
+        assert_eq!(standard("tr"), Some(2));
+        assert_eq!(standard("bl"), Some(6));
+        assert_eq!(standard("BR"), Some(8));
+        assert_eq!(standard("middle"), None);
+        // Aliases name what is on screen, so a mirrored board swaps sides
+        assert_eq!(parse_move("tl", Layout::StandardMirrored), Some(2));
+        assert_eq!(parse_move("center", Layout::NumpadMirrored), Some(4));
+    }
+
+    #[test]
+    fn coordinates_count_columns_then_rows() {
+        assert_eq!(parse_coordinate("a1"), Some(0));
+        assert_eq!(parse_coordinate("c1"), Some(2));
+        assert_eq!(parse_coordinate("b2"), Some(4));
+        assert_eq!(parse_coordinate("a3"), Some(6));
+        assert_eq!(parse_coordinate("c3"), Some(8));
+        for bad in ["d1", "a4", "1a", "b", "b22", ""] {
+            assert_eq!(parse_coordinate(bad), None, "{}", bad);
+        }
+        // Through parse_move they too follow a mirrored screen
+        assert_eq!(parse_move("A1", Layout::StandardMirrored), Some(2));
+    }
+
+    #[test]
+    fn status_line_has_the_documented_format() {
+        let model = game_of(&[0, 4]);
+        assert_eq!(
+            status_line(&model),
+            "STATUS board=o___x____ to_move=o status=notfinished legal=1,2,3,5,6,7,8"
+        );
+        let model = game_of(&[0, 4, 1, 8, 2]);
+        assert_eq!(
+            status_line(&model),
+            "STATUS board=ooo_x___x to_move=x status=win legal="
+        );
+        let mut model = game_of(&[]);
+        model.board[8] = Cell::Blocked;
+        assert!(status_line(&model).starts_with("STATUS board=________# "));
+    }
+
+    #[test]
+    fn last_move_is_highlighted() {
+        let mut model = game_of(&[0, 4, 8]);
+        model.config.highlight_last = true;
+        assert_eq!(
+            rendered(&model),
+            "0|1|2  o| | \n3|4|5   |x| \n6|7|8   | |O\n"
+        );
+        let model = apply_moves(model, &[2]).unwrap();
+        assert_eq!(
+            rendered(&model),
+            "0|1|2  o| |X\n3|4|5   |x| \n6|7|8   | |o\n"
+        );
+    }
+
+    #[test]
+    fn move_numbers_follow_the_history() {
+        let text = render_with_move_numbers(&[Cell::Unfilled; 9], &[4, 0, 8, 2]);
+        assert_eq!(text, "(2)|   |(4)\n   | 1 |   \n   |   | 3 ");
+        // Cells nobody played keep what the start showed
+        let mut start = [Cell::Unfilled; 9];
+        start[1] = Cell::Blocked;
+        let text = render_with_move_numbers(&start, &[0]);
+        assert!(text.starts_with(&format!(" 1 | {} |", Cell::Blocked)));
+    }
+
+    #[test]
+    fn evaluation_lists_each_cell_with_the_choice_in_brackets() {
+        let mut model = game_of(&[0]);
+        model.config.strategy = Strategy::Optimal {
+            randomize_ties: false,
+        };
+        assert_eq!(computer_evaluation(&model), None);
+        let model = apply_moves(model, &[4]).unwrap();
+        // Only the center saves x after a corner opening
+        assert_eq!(
+            computer_evaluation(&model).unwrap(),
+            "Computer considered: 1 loss, 2 loss, 3 loss, [4 draw], 5 loss, 6 loss, 7 loss, 8 loss"
+        );
+        let mut model = model;
+        model.config.strategy = Strategy::Random;
+        assert_eq!(
+            computer_evaluation(&model).unwrap(),
+            "Computer: random move"
+        );
+    }
+
+    #[test]
+    fn description_names_every_cell_once() {
+        let (board, _) = parse_notation("o../.x./... o").unwrap();
+        for layout in [Layout::Standard, Layout::Numpad, Layout::NumpadMirrored] {
+            let mut out = Vec::new();
+            describe_board(&mut out, &board, layout).unwrap();
+            let text = String::from_utf8(out).unwrap();
+            for (i, &content) in board.iter().enumerate() {
+                let cell = format!("Cell {}: ", layout.label_of(i));
+                assert_eq!(text.matches(&cell).count(), 1, "{:?}: {}", layout, text);
+                let name = cell_name(content);
+                assert!(text.contains(&format!("{}{}", cell, name)), "{}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn each_verbosity_level_adds_its_reports() {
+        let reports = |flags: &[&str]| {
+            let args: Vec<String> = flags.iter().map(|s| s.to_string()).collect();
+            turn_reports(&Config::from_args(&args).unwrap())
+        };
+        assert_eq!(reports(&[]), TurnReports::default());
+        let one = reports(&["-v"]);
+        assert!(one.narration && one.analysis);
+        assert!(!one.evaluation && !one.move_values && !one.best_lines);
+        let two = reports(&["-vv"]);
+        assert!(two.analysis && two.evaluation && two.move_values);
+        assert!(!two.best_lines);
+        let all = TurnReports {
+            narration: true,
+            analysis: true,
+            evaluation: true,
+            move_values: true,
+            best_lines: true,
+        };
+        assert_eq!(reports(&["-vvv"]), all);
+        assert_eq!(reports(&["--verbose"]), all);
+        assert_eq!(reports(&["--verbosity", "2"]), two);
+        // Some reports also have a flag of their own
+        assert!(reports(&["--blindfold"]).narration);
+        assert!(!reports(&["--blindfold"]).analysis);
+    }
+
+    #[test]
+    fn replay_shows_every_move_and_ends_on_the_final_board() {
+        let moves = [4, 0, 8, 2, 1, 7, 6, 3, 5];
+        let frames = replay_frames(&moves, Layout::Numpad);
+        assert_eq!(frames.len(), moves.len());
+        for (k, (frame, &i)) in frames.iter().zip(&moves).enumerate() {
+            let header = format!(
+                "Move {}: {} plays {}\n",
+                k + 1,
+                symbol_of_move(k),
+                Layout::Numpad.label_of(i)
+            );
+            assert!(frame.starts_with(&header), "{}", frame);
+        }
+        let last = board_after(&[Cell::Unfilled; 9], &moves);
+        let board = render_standard(&last, Layout::Numpad, Marks::default());
+        assert!(frames[moves.len() - 1].ends_with(&format!("{}\n", board)));
+        assert!(replay_frames(&[], Layout::Standard).is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+// Random legal games, checked after every update. A failing game shrinks
+// to the shortest list of moves that still breaks a property.
+use proptest::collection::vec;
+use proptest::prelude::*;
+use sanmoku::builder::GameBuilder;
+use sanmoku::config::Config;
+use sanmoku::helpers::has_bingo;
+use sanmoku::invariants::invariant_violation;
+use sanmoku::model::{Cell, GameStatus, Message, Model, Player};
+use sanmoku::update::update;
+
+fn cells_of(model: &Model, symbol: Cell) -> Vec<usize> {
+    (0..9).filter(|&i| model.board[i] == symbol).collect()
+}
+
+// The status the board calls for, worked out with has_bingo rather than
+// the game's own win conditions.
+fn expected_status(model: &Model) -> GameStatus {
+    let nought_line = has_bingo(cells_of(model, Cell::Nought));
+    let cross_line = has_bingo(cells_of(model, Cell::Cross));
+    let line_maker = match (nought_line, cross_line) {
+        (true, false) => Some(Cell::Nought),
+        (false, true) => Some(Cell::Cross),
+        _ => None,
+    };
+    match line_maker {
+        Some(maker) => {
+            let winner = if model.config.misere {
+                maker.opponent()
+            } else {
+                maker
+            };
+            GameStatus::Settled(if winner == model.user_symbol() {
+                Player::User
+            } else {
+                Player::Computer
+            })
+        }
+        None if !model.board.contains(&Cell::Unfilled) => GameStatus::Draw,
+        None => GameStatus::NotFinished,
+    }
+}
+
+fn check(model: &Model) -> Result<(), TestCaseError> {
+    let noughts = cells_of(model, Cell::Nought).len();
+    let crosses = cells_of(model, Cell::Cross).len();
+    prop_assert!(noughts == crosses || noughts == crosses + 1);
+    prop_assert_eq!(noughts + crosses, model.history.len());
+    prop_assert!(
+        !(has_bingo(cells_of(model, Cell::Nought)) && has_bingo(cells_of(model, Cell::Cross)))
+    );
+    prop_assert_eq!(model.status, expected_status(model));
+    prop_assert_eq!(invariant_violation(model), None);
+    Ok(())
+}
+
+// Plays the free cell each pick points at, until the picks or the game run
+// out.
+fn play_picks(mut model: Model, picks: &[usize]) -> Result<(), TestCaseError> {
+    check(&model)?;
+    for &pick in picks {
+        if model.status != GameStatus::NotFinished {
+            break;
+        }
+        let free = model.available_cells();
+        let cell = free[pick % free.len()];
+        model = update(model, Message::CellClicked(cell));
+        check(&model)?;
+    }
+    Ok(())
+}
+
+proptest! {
+    // Solo games take every move from the picks, so any legal game can come
+    // up.
+    #[test]
+    fn solo_games_keep_the_invariants(picks in vec(0usize..9, 0..10), misere in any::<bool>()) {
+        let config = Config {
+            solo: true,
+            misere,
+            ..Config::default()
+        };
+        let model = GameBuilder::from_config(config)
+            .first_player(Player::User)
+            .build()
+            .unwrap();
+        play_picks(model, &picks)?;
+    }
+
+    #[test]
+    fn games_against_the_computer_keep_the_invariants(
+        picks in vec(0usize..9, 0..5),
+        seed in any::<u64>(),
+        user_first in any::<bool>(),
+        misere in any::<bool>(),
+    ) {
+        let config = Config {
+            seed: Some(seed),
+            misere,
+            ..Config::default()
+        };
+        let first = if user_first { Player::User } else { Player::Computer };
+        let model = GameBuilder::from_config(config)
+            .first_player(first)
+            .build()
+            .unwrap();
+        play_picks(model, &picks)?;
+    }
+}